@@ -0,0 +1,162 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use num_traits::{Bounded, CheckedAdd, Num, NumCast, ToPrimitive};
+use rbset::RBSet;
+use std::{
+    collections::BTreeSet,
+    fmt::Debug,
+    ops::{AddAssign, SubAssign},
+};
+
+// values close to MIN / MAX are picked much more often than with uniform T
+#[derive(Clone, Copy, Debug, Arbitrary)]
+pub enum Value<T> {
+    Any(T),
+    NearMin(u8),
+    NearMax(u8),
+}
+
+#[derive(Clone, Debug, Arbitrary)]
+pub enum Action<T> {
+    Insert(Value<T>),
+    Remove(Value<T>),
+    // [value, value + len], saturating at MAX
+    InsertRange(Value<T>, u8),
+    RemoveRange(Value<T>, u8),
+    Check(Value<T>),
+    CheckRanges,
+}
+
+#[derive(Clone, Debug, Arbitrary)]
+pub enum Input {
+    I16(Vec<Action<i16>>),
+    I64(Vec<Action<i64>>),
+}
+
+fuzz_target!(|input: Input| {
+    match input {
+        Input::I16(actions) => run(&actions),
+        Input::I64(actions) => run(&actions),
+    }
+});
+
+trait Int:
+    Num
+    + PartialOrd
+    + Ord
+    + AddAssign
+    + SubAssign
+    + Copy
+    + ToPrimitive
+    + NumCast
+    + Bounded
+    + CheckedAdd
+    + Debug
+{
+}
+
+impl<T> Int for T where
+    T: Num
+        + PartialOrd
+        + Ord
+        + AddAssign
+        + SubAssign
+        + Copy
+        + ToPrimitive
+        + NumCast
+        + Bounded
+        + CheckedAdd
+        + Debug
+{
+}
+
+fn resolve<T: Int>(value: Value<T>) -> T {
+    match value {
+        Value::Any(value) => value,
+        Value::NearMin(offset) => T::min_value() + T::from(offset).unwrap(),
+        Value::NearMax(offset) => T::max_value() - T::from(offset).unwrap(),
+    }
+}
+
+fn range_end<T: Int>(start: T, len: u8) -> T {
+    start
+        .checked_add(&T::from(len).unwrap())
+        .unwrap_or(T::max_value())
+}
+
+// calls f for every value in [start, end] without stepping past end
+fn for_each_in<T: Int>(start: T, end: T, mut f: impl FnMut(T)) {
+    let mut x = start;
+    loop {
+        f(x);
+        if x == end {
+            break;
+        }
+        x += T::one();
+    }
+}
+
+fn run<T: Int>(actions: &[Action<T>]) {
+    let mut set = RBSet::new();
+    let mut oracle = BTreeSet::new();
+    for (idx, action) in actions.iter().enumerate() {
+        match action {
+            Action::Insert(value) => {
+                let value = resolve(*value);
+                set.insert(value);
+                oracle.insert(value);
+            }
+            Action::Remove(value) => {
+                let value = resolve(*value);
+                set.remove(&value);
+                oracle.remove(&value);
+            }
+            Action::InsertRange(value, len) => {
+                let start = resolve(*value);
+                let end = range_end(start, *len);
+                set.insert_range(start, end);
+                for_each_in(start, end, |x| {
+                    oracle.insert(x);
+                });
+            }
+            Action::RemoveRange(value, len) => {
+                let start = resolve(*value);
+                let end = range_end(start, *len);
+                set.remove_range(start, end);
+                for_each_in(start, end, |x| {
+                    oracle.remove(&x);
+                });
+            }
+            Action::Check(value) => {
+                let value = resolve(*value);
+                if set.contains(&value) != oracle.contains(&value) {
+                    panic!("check at step {idx} for {value:?} failed, actions: {actions:?}");
+                }
+                assert_eq!(set.len(), oracle.len());
+            }
+            Action::CheckRanges => {
+                let expected = runs(&oracle);
+                if set.ranges() != expected.as_slice() {
+                    panic!(
+                        "got ranges {:?}, expected {expected:?}, actions: {actions:?}",
+                        set.ranges()
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn runs<T: Int>(values: &BTreeSet<T>) -> Vec<(T, T)> {
+    let mut result: Vec<(T, T)> = Vec::new();
+    for &value in values {
+        match result.last_mut() {
+            // value > last.1, so value - 1 doesn't overflow
+            Some(last) if value - T::one() == last.1 => last.1 = value,
+            _ => result.push((value, value)),
+        }
+    }
+    result
+}
@@ -0,0 +1,38 @@
+#ifndef RBSET_H
+#define RBSET_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Set of u64 values stored as sorted inclusive ranges, see src/ffi.rs */
+typedef struct RBSetU64 RBSetU64;
+
+RBSetU64 *rbset_new(void);
+void rbset_free(RBSetU64 *set);
+
+void rbset_insert(RBSetU64 *set, uint64_t value);
+void rbset_insert_range(RBSetU64 *set, uint64_t start, uint64_t end);
+void rbset_remove(RBSetU64 *set, uint64_t value);
+void rbset_remove_range(RBSetU64 *set, uint64_t start, uint64_t end);
+void rbset_clear(RBSetU64 *set);
+
+bool rbset_contains(const RBSetU64 *set, uint64_t value);
+/* Number of values, saturates at UINT64_MAX when all 2^64 values are in the set */
+uint64_t rbset_len(const RBSetU64 *set);
+size_t rbset_range_count(const RBSetU64 *set);
+
+/* Calls f for every [start, end] range in ascending order, stops when f returns false */
+void rbset_for_each_range(const RBSetU64 *set,
+                          bool (*f)(uint64_t start, uint64_t end, void *user),
+                          void *user);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RBSET_H */
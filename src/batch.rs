@@ -0,0 +1,132 @@
+use std::{
+    collections::BTreeSet,
+    ops::{AddAssign, SubAssign},
+};
+
+use num_traits::{Num, ToPrimitive};
+
+use crate::{DifferenceRanges, RBSet, UnionRanges};
+
+/// One change to a set, see [RBSet::apply_ops].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Op<T> {
+    Insert(T),
+    Remove(T),
+    /// Inclusive, ignored if start > end.
+    InsertRange(T, T),
+    RemoveRange(T, T),
+}
+
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> RBSet<T> {
+    /// Apply ops as if one by one in order, but with one sort and one merge pass over the set.
+    ///
+    /// For every value the last op covering it decides whether it ends up in the set.
+    pub fn apply_ops(&mut self, ops: impl IntoIterator<Item = Op<T>>) {
+        // (range, is insert) in op order, idx is the priority
+        let ops: Vec<((T, T), bool)> = ops
+            .into_iter()
+            .map(|op| match op {
+                Op::Insert(x) => ((x, x), true),
+                Op::Remove(x) => ((x, x), false),
+                Op::InsertRange(start, end) => ((start, end), true),
+                Op::RemoveRange(start, end) => ((start, end), false),
+            })
+            .filter(|((start, end), _)| start <= end)
+            .collect();
+        // starts sort before ends at the same position, end events are at the last value
+        let mut events: Vec<(T, bool, usize)> = Vec::with_capacity(ops.len() * 2);
+        for (idx, ((start, end), _)) in ops.iter().enumerate() {
+            events.push((*start, false, idx));
+            events.push((*end, true, idx));
+        }
+        events.sort_unstable_by(|a, b| {
+            a.0.partial_cmp(&b.0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.1.cmp(&b.1))
+        });
+
+        let mut insert: Vec<(T, T)> = Vec::new();
+        let mut remove: Vec<(T, T)> = Vec::new();
+        let mut emit = |segment: (T, T), winner: usize| {
+            let ranges = if ops[winner].1 {
+                &mut insert
+            } else {
+                &mut remove
+            };
+            match ranges.last_mut() {
+                // segments are ascending and disjoint, so only adjacent ones need joining
+                Some(last) if segment.0 - T::one() == last.1 => last.1 = segment.1,
+                _ => ranges.push(segment),
+            }
+        };
+        // ops covering the current position and where the current segment started
+        let mut active: BTreeSet<usize> = BTreeSet::new();
+        let mut from = None;
+        let mut idx = 0;
+        while idx < events.len() {
+            let pos = events[idx].0;
+            let mut starts = idx;
+            while starts < events.len() && events[starts].0 == pos && !events[starts].1 {
+                starts += 1;
+            }
+            if starts > idx {
+                if let (Some(start), Some(&winner)) = (from, active.last()) {
+                    if start < pos {
+                        // pos - 1 >= start, can't overflow
+                        emit((start, pos - T::one()), winner);
+                    }
+                }
+                active.extend(events[idx..starts].iter().map(|e| e.2));
+                from = Some(pos);
+            }
+            let mut ends = starts;
+            while ends < events.len() && events[ends].0 == pos {
+                ends += 1;
+            }
+            if ends > starts {
+                emit((from.unwrap(), pos), *active.last().unwrap());
+                for event in &events[starts..ends] {
+                    active.remove(&event.2);
+                }
+                // an op ending after pos is still active, so pos + 1 can't overflow
+                from = if active.is_empty() {
+                    None
+                } else {
+                    Some(pos + T::one())
+                };
+            }
+            idx = ends;
+        }
+
+        let kept = DifferenceRanges::new(self.ranges.iter().copied(), remove);
+        self.ranges = UnionRanges::new(kept, insert).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_ops_win() {
+        let mut set = RBSet::<u8>::try_from(vec![(0, 10), (250, 255)]).unwrap();
+        set.apply_ops([
+            Op::RemoveRange(5, 255),
+            Op::Insert(7),
+            Op::InsertRange(20, 30),
+            Op::Remove(25),
+            Op::InsertRange(40, 30),
+            Op::Insert(255),
+            Op::RemoveRange(0, 0),
+        ]);
+        assert_eq!(
+            set.ranges(),
+            &[(1, 4), (7, 7), (20, 24), (26, 30), (255, 255)]
+        );
+        set.apply_ops([Op::RemoveRange(0, 255), Op::InsertRange(0, 255)]);
+        assert_eq!(set.ranges(), &[(0, 255)]);
+        set.apply_ops([]);
+        assert_eq!(set.ranges(), &[(0, 255)]);
+    }
+}
@@ -0,0 +1,100 @@
+use std::ops::{AddAssign, SubAssign};
+
+use num_traits::{Num, NumCast, ToPrimitive};
+
+use crate::{distance, hybrid::word_runs, RBSet};
+
+// Word slices are what bitset crates expose, e.g. bitvec's BitVec<u64, Lsb0>::as_raw_slice(),
+// so no dependency on them is needed.
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive + NumCast> RBSet<T> {
+    /// Set with value offset + i for every set bit i, bit b of `words[j]` is i = j * 64 + b.
+    ///
+    /// Runs are found a word at a time. Panics if a value doesn't fit into T.
+    pub fn from_words(words: &[u64], offset: T) -> Self {
+        let mut ranges = Vec::new();
+        word_runs(words, |start, end| {
+            let value = |bit: u64| crate::offset(offset, bit as u128);
+            ranges.push((value(start), value(end)));
+        });
+        RBSet { ranges }
+    }
+
+    /// Bitmap of values in [offset, offset + bits), in the layout of [RBSet::from_words].
+    ///
+    /// Values outside of it are ignored.
+    pub fn to_words(&self, offset: T, bits: usize) -> Vec<u64> {
+        let mut words = vec![0u64; bits.div_ceil(64)];
+        let first = self.ranges.partition_point(|range| range.1 < offset);
+        for &(start, end) in &self.ranges[first..] {
+            let lo = if start < offset {
+                0
+            } else {
+                distance(offset, start)
+            };
+            if lo >= bits as u128 {
+                break;
+            }
+            let hi = distance(offset, end).min(bits as u128 - 1);
+            set_bits(&mut words, lo as usize, hi as usize);
+        }
+        words
+    }
+}
+
+// set bits lo..=hi, whole words at once
+fn set_bits(words: &mut [u64], lo: usize, hi: usize) {
+    let (first, last) = (lo / 64, hi / 64);
+    let low_mask = u64::MAX << (lo % 64);
+    let high_mask = u64::MAX >> (63 - hi % 64);
+    if first == last {
+        words[first] |= low_mask & high_mask;
+    } else {
+        words[first] |= low_mask;
+        for word in &mut words[first + 1..last] {
+            *word = u64::MAX;
+        }
+        words[last] |= high_mask;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let set =
+            RBSet::<i32>::try_from(vec![(-100, -90), (-3, 60), (63, 63), (127, 200)]).unwrap();
+        let words = set.to_words(-3, 192);
+        assert_eq!(words.len(), 3);
+        assert_eq!(words[0], u64::MAX);
+        assert_eq!(
+            RBSet::from_words(&words, -3).ranges(),
+            &[(-3, 60), (63, 63), (127, 188)]
+        );
+    }
+
+    #[test]
+    fn partial_words() {
+        let set = RBSet::<u8>::try_from(vec![(0, 3), (10, 10), (200, 255)]).unwrap();
+        assert_eq!(set.to_words(2, 8), vec![0b11]);
+        assert_eq!(set.to_words(2, 10), vec![0b1_0000_0011]);
+        assert_eq!(set.to_words(0, 11), vec![0b100_0000_1111]);
+        assert_eq!(set.to_words(192, 64), vec![u64::MAX << 8]);
+        assert_eq!(RBSet::<u8>::from_words(&[0, 0], 0).ranges(), &[]);
+    }
+
+    #[test]
+    fn signed_round_trip() {
+        let set = RBSet::<i8>::try_from(vec![(-128, -100), (0, 0), (64, 127)]).unwrap();
+        let words = set.to_words(-128, 256);
+        assert_eq!(RBSet::from_words(&words, -128).ranges(), set.ranges());
+    }
+
+    #[test]
+    #[should_panic]
+    fn value_past_max() {
+        RBSet::<i8>::from_words(&[0b11], 126);
+        RBSet::<i8>::from_words(&[0b100], 126);
+    }
+}
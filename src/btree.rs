@@ -0,0 +1,182 @@
+use std::{
+    collections::BTreeMap,
+    ops::{AddAssign, SubAssign},
+};
+
+use num_traits::{Num, ToPrimitive};
+
+use crate::{len_of, RBSet};
+
+/// Same set as RBSet, but ranges are kept in a BTreeMap instead of a Vec.
+///
+/// Edits in the middle of a set with many ranges are O(log n) instead of shifting the Vec,
+/// at the cost of slower iteration and more memory per range.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BTreeRBSet<T> {
+    // start -> end, inclusive
+    ranges: BTreeMap<T, T>,
+}
+
+impl<T: Num + Ord + AddAssign + SubAssign + Copy + ToPrimitive> BTreeRBSet<T> {
+    pub fn new() -> Self {
+        BTreeRBSet {
+            ranges: BTreeMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, value: T) {
+        self.insert_range(value, value);
+    }
+
+    pub fn remove(&mut self, value: &T) {
+        self.remove_range(*value, *value);
+    }
+
+    /// Insert all values in [start, end], does nothing if start > end.
+    pub fn insert_range(&mut self, start: T, end: T) {
+        if start > end {
+            return;
+        }
+        let mut merged = (start, end);
+        // range before start that overlaps or touches it
+        if let Some((&prev_start, &prev_end)) = self.ranges.range(..=start).next_back() {
+            if prev_end >= start || start - T::one() == prev_end {
+                merged.0 = prev_start;
+                if prev_end > merged.1 {
+                    merged.1 = prev_end;
+                }
+            }
+        }
+        // ranges starting inside [start, end] or right after it
+        loop {
+            let next = match self.ranges.range(start..).next() {
+                Some((&next_start, &next_end))
+                    if next_start <= end || next_start - T::one() == end =>
+                {
+                    (next_start, next_end)
+                }
+                _ => break,
+            };
+            self.ranges.remove(&next.0);
+            if next.1 > merged.1 {
+                merged.1 = next.1;
+            }
+        }
+        self.ranges.insert(merged.0, merged.1);
+    }
+
+    /// Remove all values in [start, end], does nothing if start > end.
+    pub fn remove_range(&mut self, start: T, end: T) {
+        if start > end {
+            return;
+        }
+        // range starting before start can only be cut
+        if let Some((&prev_start, &prev_end)) = self.ranges.range(..start).next_back() {
+            if prev_end >= start {
+                self.ranges.insert(prev_start, start - T::one());
+                if prev_end > end {
+                    self.ranges.insert(end + T::one(), prev_end);
+                    return;
+                }
+            }
+        }
+        let inside: Vec<(T, T)> = self
+            .ranges
+            .range(start..=end)
+            .map(|(start, end)| (*start, *end))
+            .collect();
+        for (inside_start, inside_end) in inside {
+            self.ranges.remove(&inside_start);
+            if inside_end > end {
+                self.ranges.insert(end + T::one(), inside_end);
+            }
+        }
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.ranges
+            .range(..=*value)
+            .next_back()
+            .is_some_and(|(_, end)| end >= value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        len_of(self.ranges())
+    }
+
+    pub fn clear(&mut self) {
+        self.ranges.clear();
+    }
+
+    pub fn ranges(&self) -> impl DoubleEndedIterator<Item = (T, T)> + '_ {
+        self.ranges.iter().map(|(start, end)| (*start, *end))
+    }
+
+    pub fn to_set(&self) -> RBSet<T> {
+        RBSet::from_sorted_ranges_unchecked(self.ranges().collect())
+    }
+}
+
+impl<T: Copy + Ord> From<&RBSet<T>> for BTreeRBSet<T> {
+    fn from(set: &RBSet<T>) -> Self {
+        BTreeRBSet {
+            ranges: set.ranges.iter().copied().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_rbset() {
+        let mut set = BTreeRBSet::<i16>::new();
+        let mut expected = RBSet::new();
+        let ops: [(bool, i16, i16); 10] = [
+            (true, 0, 10),
+            (true, 20, 30),
+            (true, 12, 12),
+            (true, 11, 11),
+            (false, 5, 5),
+            (true, -32768, -32760),
+            (false, 25, 40),
+            (true, 32767, 32767),
+            (false, -32768, 2),
+            (true, 13, 19),
+        ];
+        for (insert, start, end) in ops {
+            if insert {
+                set.insert_range(start, end);
+                expected.insert_range(start, end);
+            } else {
+                set.remove_range(start, end);
+                expected.remove_range(start, end);
+            }
+            assert_eq!(set.to_set().ranges(), expected.ranges());
+        }
+        assert_eq!(set.len(), expected.len());
+        for x in -100..100 {
+            assert_eq!(set.contains(&x), expected.contains(&x));
+        }
+        assert!(set.contains(&32767));
+    }
+
+    #[test]
+    fn fragmented() {
+        let mut set = BTreeRBSet::<u32>::new();
+        for x in (0..20_000).step_by(2) {
+            set.insert(x);
+        }
+        for x in (1..20_000).step_by(2) {
+            set.insert(x);
+        }
+        assert_eq!(set.ranges().collect::<Vec<_>>(), vec![(0, 19_999)]);
+        set.remove(&10_000);
+        assert_eq!(set.ranges.len(), 2);
+    }
+}
@@ -0,0 +1,121 @@
+use std::ops::{AddAssign, Deref, SubAssign};
+
+use num_traits::{Num, ToPrimitive};
+
+use crate::{distance, RBSet};
+
+/// RBSet that stores at most budget ranges.
+///
+/// When a change leaves more ranges than that, the two ranges separated by the smallest gap are
+/// merged, so the set can contain values that were never inserted, but never loses inserted ones
+/// (unless removed).
+///
+/// A gap made by a removal is kept and another gap is closed instead, unless it is the only one.
+#[derive(Clone, Debug)]
+pub struct BudgetedRBSet<T> {
+    set: RBSet<T>,
+    budget: usize,
+}
+
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> BudgetedRBSet<T> {
+    /// Panics if budget is 0.
+    pub fn new(budget: usize) -> Self {
+        assert!(budget > 0, "budget must be at least one range");
+        BudgetedRBSet {
+            set: RBSet::new(),
+            budget,
+        }
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn insert(&mut self, value: T) {
+        self.set.insert(value);
+        self.compact(None);
+    }
+
+    /// Returns false if the value is still in the set, see [BudgetedRBSet::remove_range].
+    pub fn remove(&mut self, value: &T) -> bool {
+        self.remove_range(*value, *value)
+    }
+
+    pub fn insert_range(&mut self, start: T, end: T) {
+        self.set.insert_range(start, end);
+        self.compact(None);
+    }
+
+    /// Removing from the middle of a range splits it, which can in turn join two other ranges.
+    /// The new gap itself is only closed again when it is the only one, as with a budget of 1,
+    /// false is returned then.
+    pub fn remove_range(&mut self, start: T, end: T) -> bool {
+        self.set.remove_range(start, end);
+        self.compact(Some(start));
+        start > end || !self.set.overlaps(start..=end)
+    }
+
+    pub fn clear(&mut self) {
+        self.set.clear();
+    }
+
+    pub fn into_inner(self) -> RBSet<T> {
+        self.set
+    }
+
+    // keep is a value in a gap that should not be closed if there is any other
+    fn compact(&mut self, keep: Option<T>) {
+        let ranges = &mut self.set.ranges;
+        while ranges.len() > self.budget {
+            let kept = |idx: usize| {
+                keep.is_some_and(|keep| ranges[idx].1 < keep && keep < ranges[idx + 1].0)
+            };
+            // on ties the leftmost gap is closed
+            let idx = (0..ranges.len() - 1)
+                .min_by_key(|&idx| (kept(idx), distance(ranges[idx].1, ranges[idx + 1].0)))
+                .unwrap();
+            ranges[idx].1 = ranges.remove(idx + 1).1;
+        }
+    }
+}
+
+impl<T> Deref for BudgetedRBSet<T> {
+    type Target = RBSet<T>;
+
+    fn deref(&self) -> &RBSet<T> {
+        &self.set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_smallest_gap() {
+        let mut set = BudgetedRBSet::<u32>::new(3);
+        set.insert_range(0, 9);
+        set.insert_range(20, 29);
+        set.insert_range(35, 39);
+        set.insert_range(100, 109);
+        assert_eq!(set.ranges(), &[(0, 9), (20, 39), (100, 109)]);
+        // the new gap stays, the next smallest one is closed instead
+        assert!(set.remove_range(25, 26));
+        assert_eq!(set.ranges(), &[(0, 24), (27, 39), (100, 109)]);
+        assert!(!set.contains(&25));
+        set.insert(200);
+        assert_eq!(set.ranges(), &[(0, 39), (100, 109), (200, 200)]);
+        assert!(set.contains(&15));
+    }
+
+    #[test]
+    fn single_range() {
+        let mut set = BudgetedRBSet::<i8>::new(1);
+        set.insert(-128);
+        set.insert(127);
+        assert_eq!(set.ranges(), &[(-128, 127)]);
+        assert!(!set.remove(&0));
+        assert!(set.remove(&-128));
+        assert_eq!(set.ranges(), &[(-127, 127)]);
+    }
+}
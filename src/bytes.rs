@@ -0,0 +1,198 @@
+use std::{
+    marker::PhantomData,
+    mem::size_of,
+    ops::{AddAssign, SubAssign},
+};
+
+use num_traits::{FromBytes, Num, ToBytes, ToPrimitive};
+
+use crate::{check_ranges, len_of, Error, RBSet};
+
+/// Read-only set queried in place from the byte encoding produced by [RBSet::to_le_bytes].
+///
+/// Nothing is decoded up front, so a memory-mapped file can be used right away. Encoding is
+/// start and end of every range as fixed size little-endian integers, with no header.
+#[derive(Debug)]
+pub struct RBSetBytes<'a, T> {
+    bytes: &'a [u8],
+    _value: PhantomData<T>,
+}
+
+impl<T> RBSet<T>
+where
+    T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive + ToBytes,
+{
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.ranges.len() * 2 * size_of::<T>());
+        for (start, end) in &self.ranges {
+            bytes.extend_from_slice(start.to_le_bytes().as_ref());
+            bytes.extend_from_slice(end.to_le_bytes().as_ref());
+        }
+        bytes
+    }
+
+    /// FNV-1a hash of [RBSet::to_le_bytes], same on every platform.
+    ///
+    /// Equal sets of the same type always have the same fingerprint, so replicas can compare
+    /// it before exchanging ranges.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hash = FNV_OFFSET;
+        for (start, end) in &self.ranges {
+            for byte in start
+                .to_le_bytes()
+                .as_ref()
+                .iter()
+                .chain(end.to_le_bytes().as_ref())
+            {
+                hash = (hash ^ *byte as u64).wrapping_mul(FNV_PRIME);
+            }
+        }
+        hash
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+impl<'a, T> RBSetBytes<'a, T>
+where
+    T: Num
+        + PartialOrd
+        + AddAssign
+        + SubAssign
+        + Copy
+        + ToPrimitive
+        + ToBytes
+        + FromBytes<Bytes = <T as ToBytes>::Bytes>,
+{
+    /// Checks the length and that ranges are sorted and coalesced, this reads all the bytes.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, Error> {
+        let set = Self::from_le_bytes_unchecked(bytes)?;
+        check_ranges(set.ranges())?;
+        Ok(set)
+    }
+
+    /// Only checks the length, ranges must be valid as in
+    /// [RBSet::from_sorted_ranges_unchecked].
+    pub fn from_le_bytes_unchecked(bytes: &'a [u8]) -> Result<Self, Error> {
+        if !bytes.len().is_multiple_of(2 * size_of::<T>()) {
+            return Err(Error::InvalidByteLength { len: bytes.len() });
+        }
+        Ok(RBSetBytes {
+            bytes,
+            _value: PhantomData,
+        })
+    }
+
+    pub fn range_count(&self) -> usize {
+        self.bytes.len() / (2 * size_of::<T>())
+    }
+
+    /// Panics if index >= range_count().
+    pub fn range(&self, index: usize) -> (T, T) {
+        let size = size_of::<T>();
+        let offset = index * 2 * size;
+        (
+            decode(&self.bytes[offset..offset + size]),
+            decode(&self.bytes[offset + size..offset + 2 * size]),
+        )
+    }
+
+    pub fn ranges(&self) -> impl Iterator<Item = (T, T)> + '_ {
+        (0..self.range_count()).map(|index| self.range(index))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        // first range with end >= value
+        let (mut lo, mut hi) = (0, self.range_count());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.range(mid).1 < *value {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo < self.range_count() && self.range(lo).0 <= *value
+    }
+
+    pub fn len(&self) -> usize {
+        len_of(self.ranges())
+    }
+
+    pub fn to_set(&self) -> RBSet<T> {
+        RBSet::from_sorted_ranges_unchecked(self.ranges().collect())
+    }
+}
+
+impl<T> Clone for RBSetBytes<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for RBSetBytes<'_, T> {}
+
+fn decode<T: ToBytes + FromBytes<Bytes = <T as ToBytes>::Bytes> + Num>(bytes: &[u8]) -> T {
+    let mut buf = T::zero().to_le_bytes();
+    buf.as_mut().copy_from_slice(bytes);
+    T::from_le_bytes(&buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let set = RBSet::<i32>::try_from(vec![(-100, -50), (0, 0), (7, 1000)]).unwrap();
+        let bytes = set.to_le_bytes();
+        assert_eq!(bytes.len(), 3 * 8);
+        let in_place = RBSetBytes::<i32>::new(&bytes).unwrap();
+        assert_eq!(in_place.range_count(), 3);
+        assert_eq!(in_place.len(), set.len());
+        for x in -200..1100 {
+            assert_eq!(in_place.contains(&x), set.contains(&x));
+        }
+        assert_eq!(in_place.to_set().ranges(), set.ranges());
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            RBSetBytes::<u16>::new(&[0, 0, 1]).unwrap_err(),
+            Error::InvalidByteLength { len: 3 }
+        );
+        let bytes = RBSet::from_sorted_ranges_unchecked(vec![(0u16, 5), (5, 7)]).to_le_bytes();
+        assert_eq!(
+            RBSetBytes::<u16>::new(&bytes).unwrap_err(),
+            Error::NotCoalesced { index: 1 }
+        );
+        assert!(RBSetBytes::<u16>::new(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn fingerprint() {
+        // reference FNV-1a values, these must not change
+        assert_eq!(RBSet::<u8>::new().fingerprint(), 0xcbf2_9ce4_8422_2325);
+        let set = RBSet::<u8>::try_from(vec![(1, 2)]).unwrap();
+        assert_eq!(set.fingerprint(), 0x82f2407b4e8902a);
+        let mut other = RBSet::<u8>::new();
+        other.insert(2);
+        other.insert(1);
+        assert_eq!(set.fingerprint(), other.fingerprint());
+        other.insert(3);
+        assert_ne!(set.fingerprint(), other.fingerprint());
+        // multi-byte values are hashed little endian on every platform
+        let wide =
+            RBSet::<u32>::try_from(vec![(0x0102_0304, 0x0a0b_0c0d), (0xffff_fff0, u32::MAX)])
+                .unwrap();
+        assert_eq!(wide.fingerprint(), 0xd09b_1eee_a371_966a);
+        let signed = RBSet::<i64>::try_from(vec![(-300, -1), (1 << 40, (1 << 40) + 3)]).unwrap();
+        assert_eq!(signed.fingerprint(), 0x26cb_b271_81fb_d822);
+    }
+}
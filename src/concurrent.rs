@@ -0,0 +1,115 @@
+use std::{
+    ops::{AddAssign, SubAssign},
+    sync::RwLock,
+};
+
+use num_traits::{Num, ToPrimitive};
+
+use crate::RBSet;
+
+/// RBSet that can be shared between threads.
+///
+/// Values are split into blocks of `block_len` consecutive values, blocks are assigned to
+/// shards round-robin and each shard is guarded by its own lock. Threads working on
+/// different blocks rarely contend.
+#[derive(Debug)]
+pub struct ConcurrentRBSet<T> {
+    shards: Vec<RwLock<RBSet<T>>>,
+    block_len: T,
+}
+
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> ConcurrentRBSet<T> {
+    /// Panics if `shards` is 0 or `block_len` is not positive.
+    pub fn new(shards: usize, block_len: T) -> Self {
+        assert!(shards > 0, "shards must be > 0");
+        assert!(block_len > T::zero(), "block_len must be > 0");
+        ConcurrentRBSet {
+            shards: (0..shards).map(|_| RwLock::new(RBSet::new())).collect(),
+            block_len,
+        }
+    }
+
+    pub fn insert(&self, value: T) {
+        self.shard(&value).write().unwrap().insert(value);
+    }
+
+    pub fn remove(&self, value: &T) {
+        self.shard(value).write().unwrap().remove(value);
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.shard(value).read().unwrap().contains(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|s| s.read().unwrap().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|s| s.read().unwrap().is_empty())
+    }
+
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            shard.write().unwrap().clear();
+        }
+    }
+
+    /// Merge all shards into one set, shards are locked one by one, so concurrent
+    /// modifications may or may not be observed.
+    pub fn to_set(&self) -> RBSet<T> {
+        let mut ranges = Vec::new();
+        for shard in &self.shards {
+            ranges.extend_from_slice(shard.read().unwrap().ranges());
+        }
+        RBSet::try_from(ranges).expect("shards contain valid ranges")
+    }
+
+    fn shard(&self, value: &T) -> &RwLock<RBSet<T>> {
+        let block = *value / self.block_len;
+        let shards = self.shards.len();
+        let idx = match block.to_i128() {
+            Some(block) => block.rem_euclid(shards as i128) as usize,
+            // u128 blocks past i128::MAX
+            None => (block.to_u128().unwrap() % shards as u128) as usize,
+        };
+        &self.shards[idx]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concurrent_insert() {
+        let set = ConcurrentRBSet::<i64>::new(8, 16);
+        std::thread::scope(|s| {
+            for t in 0..4i64 {
+                let set = &set;
+                s.spawn(move || {
+                    for x in (-1000..1000i64).filter(|x| x.rem_euclid(4) == t) {
+                        set.insert(x);
+                    }
+                });
+            }
+        });
+        assert_eq!(set.len(), 2000);
+        assert!(set.contains(&-1000));
+        assert!(!set.contains(&1000));
+        set.remove(&0);
+        assert_eq!(set.to_set().ranges(), &[(-1000, -1), (1, 999)]);
+    }
+
+    #[test]
+    fn large_u128() {
+        let set = ConcurrentRBSet::<u128>::new(3, 1);
+        set.insert(u128::MAX);
+        set.insert(1 << 127);
+        assert!(set.contains(&u128::MAX));
+        assert_eq!(
+            set.to_set().ranges(),
+            &[(1 << 127, 1 << 127), (u128::MAX, u128::MAX)]
+        );
+    }
+}
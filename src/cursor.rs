@@ -0,0 +1,149 @@
+use std::ops::{AddAssign, SubAssign};
+
+use num_traits::{Num, ToPrimitive};
+
+use crate::{partition_point_near, RBSet};
+
+/// Mutable cursor over the ranges of an RBSet, see [RBSet::cursor_at].
+///
+/// Cursor points at one of the ranges or past the last one. Lookups and edits search
+/// from the current position, so working on nearby values is cheap.
+#[derive(Debug)]
+pub struct RBSetCursor<'a, T> {
+    set: &'a mut RBSet<T>,
+    pos: usize,
+}
+
+impl<'a, T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> RBSetCursor<'a, T> {
+    pub(crate) fn new(set: &'a mut RBSet<T>, pos: usize) -> Self {
+        RBSetCursor { set, pos }
+    }
+
+    /// Range under the cursor, None if the cursor is past the last range.
+    pub fn range(&self) -> Option<(T, T)> {
+        self.set.ranges.get(self.pos).copied()
+    }
+
+    /// Index of the current range in [RBSet::ranges].
+    pub fn index(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns false if already past the last range.
+    pub fn move_next(&mut self) -> bool {
+        if self.pos < self.set.ranges.len() {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns false if already at the first range.
+    pub fn move_prev(&mut self) -> bool {
+        if self.pos > 0 {
+            self.pos -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Move to the range containing value, or to the first range after it.
+    pub fn seek(&mut self, value: &T) {
+        self.pos = partition_point_near(&self.set.ranges, Some(self.pos), |r| r.1 < *value);
+    }
+
+    /// Missing values between the previous and the current range.
+    pub fn gap_before(&self) -> Option<(T, T)> {
+        if self.pos == 0 {
+            return None;
+        }
+        let prev = self.set.ranges[self.pos - 1];
+        let current = self.set.ranges.get(self.pos)?;
+        Some((prev.1 + T::one(), current.0 - T::one()))
+    }
+
+    /// Missing values between the current and the next range.
+    pub fn gap_after(&self) -> Option<(T, T)> {
+        let current = self.set.ranges.get(self.pos)?;
+        let next = self.set.ranges.get(self.pos + 1)?;
+        Some((current.1 + T::one(), next.0 - T::one()))
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        let idx = partition_point_near(&self.set.ranges, Some(self.pos), |r| r.1 < *value);
+        self.set.ranges.get(idx).is_some_and(|r| r.0 <= *value)
+    }
+
+    /// Cursor is moved to the range containing value.
+    pub fn insert(&mut self, value: T) {
+        self.insert_range(value, value);
+    }
+
+    /// Cursor is moved to the first range after value.
+    pub fn remove(&mut self, value: &T) {
+        self.remove_range(*value, *value);
+    }
+
+    /// Cursor is moved to the range containing start.
+    pub fn insert_range(&mut self, start: T, end: T) {
+        if let Some(edit) = self.set.insert_range_edit(start, end, Some(self.pos)) {
+            self.pos = edit.window.start;
+            self.set.apply_edit(edit);
+        }
+    }
+
+    /// Cursor is moved to the first range after end.
+    pub fn remove_range(&mut self, start: T, end: T) {
+        match self.set.remove_range_edit(start, end, Some(self.pos)) {
+            Some(edit) => {
+                self.pos = edit.window.start + usize::from(edit.replacement[0].is_some());
+                self.set.apply_edit(edit);
+            }
+            None => self.seek(&start),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walk() {
+        let mut set = RBSet::<u32>::try_from(vec![(0, 3), (7, 8), (10, 10)]).unwrap();
+        let mut cursor = set.cursor_at(&5);
+        assert_eq!(cursor.range(), Some((7, 8)));
+        assert_eq!(cursor.gap_before(), Some((4, 6)));
+        assert_eq!(cursor.gap_after(), Some((9, 9)));
+        assert!(cursor.move_next());
+        assert_eq!(cursor.range(), Some((10, 10)));
+        assert_eq!(cursor.gap_after(), None);
+        assert!(cursor.move_next());
+        assert_eq!(cursor.range(), None);
+        assert!(!cursor.move_next());
+        cursor.seek(&1);
+        assert_eq!(cursor.index(), 0);
+        assert!(!cursor.move_prev());
+        assert!(cursor.contains(&10));
+        assert!(!cursor.contains(&9));
+    }
+
+    #[test]
+    fn edit() {
+        let mut set = RBSet::<i32>::try_from(vec![(0, 3), (7, 8), (10, 10)]).unwrap();
+        let mut cursor = set.cursor_at(&8);
+        cursor.insert(9);
+        assert_eq!(cursor.range(), Some((7, 10)));
+        cursor.remove(&2);
+        assert_eq!(cursor.range(), Some((3, 3)));
+        cursor.insert_range(-5, -2);
+        assert_eq!(cursor.range(), Some((-5, -2)));
+        cursor.remove_range(-3, 7);
+        assert_eq!(cursor.range(), Some((8, 10)));
+        cursor.remove_range(20, 30);
+        assert_eq!(cursor.range(), None);
+        assert_eq!(set.ranges(), &[(-5, -4), (8, 10)]);
+    }
+}
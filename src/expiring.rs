@@ -0,0 +1,203 @@
+use std::{
+    ops::{AddAssign, SubAssign},
+    time::Instant,
+};
+
+use num_traits::{Num, ToPrimitive};
+
+use crate::{len_of, RBSet};
+
+/// Set where every range remembers when it was inserted, so that old values can be purged.
+///
+/// Ranges inserted at different times are kept apart, re-inserting a value refreshes its time.
+/// Time can be any ordered type, [Instant] by default.
+#[derive(Clone, Debug)]
+pub struct ExpiringRBSet<T, I = Instant> {
+    // sorted and non-overlapping, adjacent entries only have different timestamps
+    entries: Vec<(T, T, I)>,
+}
+
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive, I: Ord + Copy>
+    ExpiringRBSet<T, I>
+{
+    pub const fn new() -> Self {
+        ExpiringRBSet {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn insert(&mut self, value: T, at: I) {
+        self.insert_range(value, value, at);
+    }
+
+    /// Insert all values in [start, end] with time at, does nothing if start > end.
+    pub fn insert_range(&mut self, start: T, end: T, at: I) {
+        if start > end {
+            return;
+        }
+        let (first, last) = self.overlapping(start, end);
+        let mut replacement = Vec::with_capacity(3);
+        if first < last && self.entries[first].0 < start {
+            let left = self.entries[first];
+            replacement.push((left.0, start - T::one(), left.2));
+        }
+        replacement.push((start, end, at));
+        if first < last && self.entries[last - 1].1 > end {
+            let right = self.entries[last - 1];
+            replacement.push((end + T::one(), right.1, right.2));
+        }
+        self.entries.splice(first..last, replacement);
+        // join with neighbours inserted at the same time
+        let idx = first + (self.entries[first].0 < start) as usize;
+        if idx + 1 < self.entries.len() && self.joins(idx) {
+            self.entries[idx].1 = self.entries.remove(idx + 1).1;
+        }
+        if idx > 0 && self.joins(idx - 1) {
+            self.entries[idx - 1].1 = self.entries.remove(idx).1;
+        }
+    }
+
+    /// Remove all values in [start, end], does nothing if start > end.
+    pub fn remove_range(&mut self, start: T, end: T) {
+        if start > end {
+            return;
+        }
+        let (first, last) = self.overlapping(start, end);
+        let mut replacement = Vec::with_capacity(2);
+        if first < last && self.entries[first].0 < start {
+            let left = self.entries[first];
+            replacement.push((left.0, start - T::one(), left.2));
+        }
+        if first < last && self.entries[last - 1].1 > end {
+            let right = self.entries[last - 1];
+            replacement.push((end + T::one(), right.1, right.2));
+        }
+        self.entries.splice(first..last, replacement);
+    }
+
+    /// Remove all values inserted before at.
+    pub fn purge_older_than(&mut self, at: I) {
+        self.entries.retain(|entry| entry.2 >= at);
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        let idx = self.entries.partition_point(|entry| entry.1 < *value);
+        self.entries.get(idx).is_some_and(|entry| entry.0 <= *value)
+    }
+
+    /// Time when value was last inserted.
+    pub fn inserted_at(&self, value: &T) -> Option<I> {
+        let idx = self.entries.partition_point(|entry| entry.1 < *value);
+        self.entries
+            .get(idx)
+            .filter(|entry| entry.0 <= *value)
+            .map(|entry| entry.2)
+    }
+
+    /// Time of the oldest value in the set.
+    pub fn oldest(&self) -> Option<I> {
+        self.entries.iter().map(|entry| entry.2).min()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        len_of(self.entries.iter().map(|(start, end, _)| (*start, *end)))
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Ranges with their insertion time.
+    pub fn entries(&self) -> &[(T, T, I)] {
+        &self.entries
+    }
+
+    /// Values without timestamps, ranges inserted at different times are coalesced.
+    pub fn to_set(&self) -> RBSet<T> {
+        let mut ranges: Vec<(T, T)> = Vec::with_capacity(self.entries.len());
+        for (start, end, _) in &self.entries {
+            match ranges.last_mut() {
+                Some(last) if *start - T::one() == last.1 => last.1 = *end,
+                _ => ranges.push((*start, *end)),
+            }
+        }
+        RBSet::from_sorted_ranges_unchecked(ranges)
+    }
+
+    // window of entries overlapping [start, end]
+    fn overlapping(&self, start: T, end: T) -> (usize, usize) {
+        let first = self.entries.partition_point(|entry| entry.1 < start);
+        let last = first + self.entries[first..].partition_point(|entry| entry.0 <= end);
+        (first, last)
+    }
+
+    // entry idx is directly followed by entry idx + 1 with the same time
+    fn joins(&self, idx: usize) -> bool {
+        let (left, right) = (&self.entries[idx], &self.entries[idx + 1]);
+        left.2 == right.2 && right.0 - T::one() == left.1
+    }
+}
+
+impl<T, I> Default for ExpiringRBSet<T, I> {
+    fn default() -> Self {
+        ExpiringRBSet {
+            entries: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn purge() {
+        let mut set = ExpiringRBSet::<u32, u32>::new();
+        set.insert_range(0, 9, 1);
+        set.insert_range(10, 19, 1);
+        set.insert_range(20, 29, 2);
+        assert_eq!(set.entries(), &[(0, 19, 1), (20, 29, 2)]);
+        // refresh the middle of the older range
+        set.insert_range(5, 7, 3);
+        assert_eq!(
+            set.entries(),
+            &[(0, 4, 1), (5, 7, 3), (8, 19, 1), (20, 29, 2)]
+        );
+        assert_eq!(set.inserted_at(&6), Some(3));
+        assert_eq!(set.to_set().ranges(), &[(0, 29)]);
+        assert_eq!(set.oldest(), Some(1));
+
+        set.purge_older_than(2);
+        assert_eq!(set.entries(), &[(5, 7, 3), (20, 29, 2)]);
+        assert!(!set.contains(&0));
+        assert!(set.contains(&25));
+        assert_eq!(set.len(), 13);
+    }
+
+    #[test]
+    fn insert_and_remove_join() {
+        let mut set = ExpiringRBSet::<i8, u8>::new();
+        set.insert_range(-128, 0, 1);
+        set.insert_range(2, 127, 1);
+        set.insert(1, 1);
+        assert_eq!(set.entries(), &[(-128, 127, 1)]);
+        set.insert_range(-10, 10, 2);
+        set.remove_range(-128, -5);
+        assert_eq!(set.entries(), &[(-4, 10, 2), (11, 127, 1)]);
+        set.remove_range(0, 127);
+        assert_eq!(set.entries(), &[(-4, -1, 2)]);
+    }
+
+    #[test]
+    fn instants() {
+        let start = Instant::now();
+        let mut set = ExpiringRBSet::<u64>::new();
+        set.insert_range(100, 200, start);
+        set.purge_older_than(start);
+        assert_eq!(set.len(), 101);
+    }
+}
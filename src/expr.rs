@@ -0,0 +1,167 @@
+use std::ops::{AddAssign, SubAssign};
+
+use num_traits::{Num, ToPrimitive};
+
+use crate::{DifferenceRanges, IntersectionRanges, RBSet, RBSetView, SliceRanges, UnionRanges};
+
+/// Set expression that is evaluated lazily, without building intermediate sets.
+///
+/// ```
+/// use rbset::{rbset, RBSet, SetExpr};
+///
+/// let allow: RBSet<u16> = rbset![0..=1023, 8080];
+/// let local: RBSet<u16> = rbset![0..=100];
+/// let deny: RBSet<u16> = rbset![22];
+/// let rule = allow.intersect_expr(&local).difference_expr(&deny);
+/// assert!(rule.contains(&80));
+/// assert!(!rule.contains(&22));
+/// assert_eq!(rule.ranges().collect::<Vec<_>>(), vec![(0, 21), (23, 100)]);
+/// ```
+pub trait SetExpr<T> {
+    type Ranges<'a>: Iterator<Item = (T, T)>
+    where
+        Self: 'a;
+
+    fn contains(&self, value: &T) -> bool;
+
+    /// Sorted and coalesced ranges of the result.
+    fn ranges(&self) -> Self::Ranges<'_>;
+
+    fn union_expr<E: SetExpr<T>>(self, other: E) -> Union<Self, E>
+    where
+        Self: Sized,
+    {
+        Union(self, other)
+    }
+
+    fn intersect_expr<E: SetExpr<T>>(self, other: E) -> Intersection<Self, E>
+    where
+        Self: Sized,
+    {
+        Intersection(self, other)
+    }
+
+    fn difference_expr<E: SetExpr<T>>(self, other: E) -> Difference<Self, E>
+    where
+        Self: Sized,
+    {
+        Difference(self, other)
+    }
+
+    fn to_set(&self) -> RBSet<T> {
+        RBSet {
+            ranges: self.ranges().collect(),
+        }
+    }
+}
+
+/// See [SetExpr::union_expr].
+#[derive(Clone, Copy, Debug)]
+pub struct Union<A, B>(A, B);
+
+/// See [SetExpr::intersect_expr].
+#[derive(Clone, Copy, Debug)]
+pub struct Intersection<A, B>(A, B);
+
+/// See [SetExpr::difference_expr].
+#[derive(Clone, Copy, Debug)]
+pub struct Difference<A, B>(A, B);
+
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> SetExpr<T> for &RBSet<T> {
+    type Ranges<'a>
+        = SliceRanges<'a, T>
+    where
+        Self: 'a;
+
+    fn contains(&self, value: &T) -> bool {
+        RBSet::contains(self, value)
+    }
+
+    fn ranges(&self) -> Self::Ranges<'_> {
+        self.ranges.iter().copied()
+    }
+}
+
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> SetExpr<T>
+    for RBSetView<'_, T>
+{
+    type Ranges<'a>
+        = SliceRanges<'a, T>
+    where
+        Self: 'a;
+
+    fn contains(&self, value: &T) -> bool {
+        RBSetView::contains(self, value)
+    }
+
+    fn ranges(&self) -> Self::Ranges<'_> {
+        RBSetView::ranges(self).iter().copied()
+    }
+}
+
+impl<T: Num + PartialOrd + Copy, A: SetExpr<T>, B: SetExpr<T>> SetExpr<T> for Union<A, B> {
+    type Ranges<'a>
+        = UnionRanges<A::Ranges<'a>, B::Ranges<'a>>
+    where
+        Self: 'a;
+
+    fn contains(&self, value: &T) -> bool {
+        self.0.contains(value) || self.1.contains(value)
+    }
+
+    fn ranges(&self) -> Self::Ranges<'_> {
+        UnionRanges::new(self.0.ranges(), self.1.ranges())
+    }
+}
+
+impl<T: Num + PartialOrd + Copy, A: SetExpr<T>, B: SetExpr<T>> SetExpr<T> for Intersection<A, B> {
+    type Ranges<'a>
+        = IntersectionRanges<A::Ranges<'a>, B::Ranges<'a>>
+    where
+        Self: 'a;
+
+    fn contains(&self, value: &T) -> bool {
+        self.0.contains(value) && self.1.contains(value)
+    }
+
+    fn ranges(&self) -> Self::Ranges<'_> {
+        IntersectionRanges::new(self.0.ranges(), self.1.ranges())
+    }
+}
+
+impl<T: Num + PartialOrd + Copy, A: SetExpr<T>, B: SetExpr<T>> SetExpr<T> for Difference<A, B> {
+    type Ranges<'a>
+        = DifferenceRanges<A::Ranges<'a>, B::Ranges<'a>>
+    where
+        Self: 'a;
+
+    fn contains(&self, value: &T) -> bool {
+        self.0.contains(value) && !self.1.contains(value)
+    }
+
+    fn ranges(&self) -> Self::Ranges<'_> {
+        DifferenceRanges::new(self.0.ranges(), self.1.ranges())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested() {
+        let a = RBSet::<i32>::try_from(vec![(0, 10), (40, 50)]).unwrap();
+        let b = RBSet::<i32>::try_from(vec![(5, 20)]).unwrap();
+        let c = RBSet::<i32>::try_from(vec![(8, 45)]).unwrap();
+        let d = RBSet::<i32>::try_from(vec![(12, 12)]).unwrap();
+        let expr = a
+            .union_expr(&b)
+            .intersect_expr(c.as_view())
+            .difference_expr(&d);
+        let expected = a.union(&b).intersection(&c).difference(&d);
+        assert_eq!(expr.to_set().ranges(), expected.ranges());
+        for x in -5..60 {
+            assert_eq!(expr.contains(&x), expected.contains(&x));
+        }
+    }
+}
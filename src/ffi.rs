@@ -0,0 +1,153 @@
+//! C API over `RBSet<u64>`, enabled with the `ffi` feature.
+//!
+//! Matching declarations are in `include/rbset.h`. Build with
+//! `cargo rustc --release --features ffi --crate-type staticlib` to get a library to link.
+
+use std::ffi::c_void;
+
+use crate::{count_values, RBSet};
+
+/// Opaque handle, only used through a pointer.
+pub struct RBSetU64 {
+    set: RBSet<u64>,
+}
+
+/// Allocate an empty set, free it with [rbset_free].
+#[no_mangle]
+pub extern "C" fn rbset_new() -> *mut RBSetU64 {
+    Box::into_raw(Box::new(RBSetU64 { set: RBSet::new() }))
+}
+
+/// # Safety
+/// `set` must be returned by [rbset_new] and not freed yet, or null.
+#[no_mangle]
+pub unsafe extern "C" fn rbset_free(set: *mut RBSetU64) {
+    if !set.is_null() {
+        drop(Box::from_raw(set));
+    }
+}
+
+/// # Safety
+/// `set` must be a valid pointer returned by [rbset_new].
+#[no_mangle]
+pub unsafe extern "C" fn rbset_insert(set: *mut RBSetU64, value: u64) {
+    (*set).set.insert(value);
+}
+
+/// Inserts [start, end], does nothing if start > end.
+///
+/// # Safety
+/// `set` must be a valid pointer returned by [rbset_new].
+#[no_mangle]
+pub unsafe extern "C" fn rbset_insert_range(set: *mut RBSetU64, start: u64, end: u64) {
+    (*set).set.insert_range(start, end);
+}
+
+/// # Safety
+/// `set` must be a valid pointer returned by [rbset_new].
+#[no_mangle]
+pub unsafe extern "C" fn rbset_remove(set: *mut RBSetU64, value: u64) {
+    (*set).set.remove(&value);
+}
+
+/// Removes [start, end], does nothing if start > end.
+///
+/// # Safety
+/// `set` must be a valid pointer returned by [rbset_new].
+#[no_mangle]
+pub unsafe extern "C" fn rbset_remove_range(set: *mut RBSetU64, start: u64, end: u64) {
+    (*set).set.remove_range(start, end);
+}
+
+/// # Safety
+/// `set` must be a valid pointer returned by [rbset_new].
+#[no_mangle]
+pub unsafe extern "C" fn rbset_clear(set: *mut RBSetU64) {
+    (*set).set.clear();
+}
+
+/// # Safety
+/// `set` must be a valid pointer returned by [rbset_new].
+#[no_mangle]
+pub unsafe extern "C" fn rbset_contains(set: *const RBSetU64, value: u64) -> bool {
+    (*set).set.contains(&value)
+}
+
+/// Number of values, saturates at UINT64_MAX as a set with all 2^64 values doesn't fit.
+///
+/// # Safety
+/// `set` must be a valid pointer returned by [rbset_new].
+#[no_mangle]
+pub unsafe extern "C" fn rbset_len(set: *const RBSetU64) -> u64 {
+    // not RBSet::len, it panics when the count doesn't fit into usize, and a panic can't
+    // unwind into C
+    let len = count_values((*set).set.ranges().iter().copied());
+    u64::try_from(len).unwrap_or(u64::MAX)
+}
+
+/// # Safety
+/// `set` must be a valid pointer returned by [rbset_new].
+#[no_mangle]
+pub unsafe extern "C" fn rbset_range_count(set: *const RBSetU64) -> usize {
+    (*set).set.ranges().len()
+}
+
+/// Calls `f(start, end, user)` for every range in ascending order, stops early if f returns
+/// false.
+///
+/// # Safety
+/// `set` must be a valid pointer returned by [rbset_new], `f` must not modify the set.
+#[no_mangle]
+pub unsafe extern "C" fn rbset_for_each_range(
+    set: *const RBSetU64,
+    f: extern "C" fn(start: u64, end: u64, user: *mut c_void) -> bool,
+    user: *mut c_void,
+) {
+    for (start, end) in (*set).set.ranges() {
+        if !f(*start, *end, user) {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern "C" fn collect(start: u64, end: u64, user: *mut c_void) -> bool {
+        let ranges = unsafe { &mut *(user as *mut Vec<(u64, u64)>) };
+        ranges.push((start, end));
+        true
+    }
+
+    #[test]
+    fn c_api() {
+        unsafe {
+            let set = rbset_new();
+            rbset_insert(set, 1);
+            rbset_insert_range(set, 5, 10);
+            rbset_remove(set, 7);
+            assert!(rbset_contains(set, 5));
+            assert!(!rbset_contains(set, 7));
+            assert_eq!(rbset_len(set), 6);
+            assert_eq!(rbset_range_count(set), 3);
+            let mut ranges: Vec<(u64, u64)> = Vec::new();
+            rbset_for_each_range(set, collect, &mut ranges as *mut _ as *mut c_void);
+            assert_eq!(ranges, vec![(1, 1), (5, 6), (8, 10)]);
+            rbset_free(set);
+        }
+    }
+
+    #[test]
+    fn full_len() {
+        unsafe {
+            let set = rbset_new();
+            rbset_insert_range(set, 1, u64::MAX);
+            assert_eq!(rbset_len(set), u64::MAX);
+            rbset_insert(set, 0);
+            assert_eq!(rbset_len(set), u64::MAX);
+            assert_eq!(rbset_range_count(set), 1);
+            rbset_free(set);
+        }
+    }
+}
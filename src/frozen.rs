@@ -0,0 +1,211 @@
+use std::{
+    ops::{AddAssign, Range, SubAssign},
+    sync::Arc,
+};
+
+use num_traits::{Num, ToPrimitive};
+
+use crate::{len_of, RBSet};
+
+// max number of ranges in one shared chunk
+const CHUNK_LEN: usize = 64;
+
+type Chunk<T> = Arc<[(T, T)]>;
+
+/// Immutable RBSet with cheap clone, modifications produce a new version.
+///
+/// Ranges are stored in reference counted chunks, a new version only copies the chunks
+/// touched by the modification and shares the rest with the previous one.
+#[derive(Debug)]
+pub struct FrozenRBSet<T> {
+    chunks: Arc<[Chunk<T>]>,
+}
+
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> FrozenRBSet<T> {
+    pub fn new() -> Self {
+        FrozenRBSet {
+            chunks: Arc::new([]),
+        }
+    }
+
+    pub fn with_inserted(&self, value: T) -> Self {
+        self.with_inserted_range(value, value)
+    }
+
+    pub fn with_removed(&self, value: &T) -> Self {
+        self.with_removed_range(*value, *value)
+    }
+
+    pub fn with_inserted_range(&self, start: T, end: T) -> Self {
+        if start > end {
+            return self.clone();
+        }
+        let chunks = &self.chunks;
+        let window = if chunks.is_empty() {
+            0..0
+        } else {
+            // same search as in RBSet::insert_range, but over chunks
+            let first = chunks.partition_point(|c| {
+                let last_end = c[c.len() - 1].1;
+                !(last_end >= start || start - T::one() == last_end)
+            });
+            let last = chunks.partition_point(|c| c[0].0 <= end || c[0].0 - T::one() == end);
+            if first < last {
+                first..last
+            } else if first < chunks.len() {
+                first..first + 1
+            } else {
+                first - 1..first
+            }
+        };
+        self.rebuild(window, |set| set.insert_range(start, end))
+    }
+
+    pub fn with_removed_range(&self, start: T, end: T) -> Self {
+        if start > end {
+            return self.clone();
+        }
+        let first = self.chunks.partition_point(|c| c[c.len() - 1].1 < start);
+        let last = self.chunks.partition_point(|c| c[0].0 <= end);
+        if first >= last {
+            return self.clone();
+        }
+        self.rebuild(first..last, |set| set.remove_range(start, end))
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        let idx = self.chunks.partition_point(|c| c[c.len() - 1].1 < *value);
+        let Some(chunk) = self.chunks.get(idx) else {
+            return false;
+        };
+        let idx = chunk.partition_point(|r| r.1 < *value);
+        chunk[idx].0 <= *value
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        len_of(self.ranges())
+    }
+
+    pub fn ranges(&self) -> impl Iterator<Item = (T, T)> + '_ {
+        self.chunks.iter().flat_map(|c| c.iter().copied())
+    }
+
+    pub fn to_set(&self) -> RBSet<T> {
+        RBSet::from_sorted_ranges_unchecked(self.ranges().collect())
+    }
+
+    // replace chunks[window] with the result of applying f to their ranges
+    fn rebuild(&self, window: Range<usize>, f: impl FnOnce(&mut RBSet<T>)) -> Self {
+        let mut set = RBSet::from_sorted_ranges_unchecked(
+            self.chunks[window.clone()]
+                .iter()
+                .flat_map(|c| c.iter().copied())
+                .collect(),
+        );
+        f(&mut set);
+        let mut chunks = Vec::with_capacity(self.chunks.len() + 1);
+        chunks.extend(self.chunks[..window.start].iter().cloned());
+        chunks.extend(set.ranges.chunks(CHUNK_LEN).map(Arc::from));
+        chunks.extend(self.chunks[window.end..].iter().cloned());
+        FrozenRBSet {
+            chunks: chunks.into(),
+        }
+    }
+}
+
+impl<T> Clone for FrozenRBSet<T> {
+    fn clone(&self) -> Self {
+        FrozenRBSet {
+            chunks: self.chunks.clone(),
+        }
+    }
+}
+
+impl<T> Default for FrozenRBSet<T> {
+    fn default() -> Self {
+        FrozenRBSet {
+            chunks: Arc::new([]),
+        }
+    }
+}
+
+impl<T: Copy> From<&RBSet<T>> for FrozenRBSet<T> {
+    fn from(set: &RBSet<T>) -> Self {
+        FrozenRBSet {
+            chunks: set.ranges.chunks(CHUNK_LEN).map(Arc::from).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions() {
+        let v0 = FrozenRBSet::<u32>::new();
+        let v1 = v0.with_inserted(5).with_inserted(6).with_inserted(10);
+        let v2 = v1.with_removed(&5).with_inserted_range(0, 3);
+        assert!(v0.is_empty());
+        assert_eq!(v1.to_set().ranges(), &[(5, 6), (10, 10)]);
+        assert_eq!(v2.to_set().ranges(), &[(0, 3), (6, 6), (10, 10)]);
+        assert!(v2.contains(&6));
+        assert!(!v2.contains(&5));
+        assert_eq!(v2.len(), 6);
+    }
+
+    #[test]
+    fn len_full_range() {
+        assert_eq!(
+            FrozenRBSet::<u8>::new().with_inserted_range(0, 255).len(),
+            256
+        );
+        assert_eq!(
+            FrozenRBSet::<i8>::new()
+                .with_inserted_range(-100, 100)
+                .len(),
+            201
+        );
+    }
+
+    #[test]
+    fn structural_sharing() {
+        let set = RBSet::from_sorted_iter((0..10_000u32).map(|x| x * 2));
+        let v0 = FrozenRBSet::from(&set);
+        let v1 = v0.with_inserted(1001).with_removed(&5000);
+        let shared = v0
+            .chunks
+            .iter()
+            .zip(v1.chunks.iter())
+            .filter(|(a, b)| Arc::ptr_eq(a, b))
+            .count();
+        assert!(shared >= v0.chunks.len() - 2);
+
+        let mut expected = set.clone();
+        expected.insert(1001);
+        expected.remove(&5000);
+        assert_eq!(v1.to_set().ranges(), expected.ranges());
+        assert_eq!(v0.to_set().ranges(), set.ranges());
+    }
+
+    #[test]
+    fn across_chunks() {
+        let set = RBSet::from_sorted_iter((0..1000i64).map(|x| x * 3));
+        let v = FrozenRBSet::from(&set)
+            .with_inserted_range(-10, 500)
+            .with_removed_range(1000, 2000)
+            .with_inserted_range(5000, 6000);
+        let mut expected = set;
+        expected.insert_range(-10, 500);
+        expected.remove_range(1000, 2000);
+        expected.insert_range(5000, 6000);
+        assert_eq!(v.to_set().ranges(), expected.ranges());
+        for x in -20..6100 {
+            assert_eq!(v.contains(&x), expected.contains(&x));
+        }
+    }
+}
@@ -0,0 +1,416 @@
+use std::{
+    marker::PhantomData,
+    ops::{AddAssign, SubAssign},
+};
+
+use num_traits::{Num, NumCast, ToPrimitive};
+
+use crate::RBSet;
+
+// values are split into blocks of 2^16, each block is stored in its own container
+const BLOCK_BITS: u32 = 16;
+const BLOCK_MASK: i128 = (1 << BLOCK_BITS) - 1;
+const WORDS: usize = (1 << BLOCK_BITS) / 64;
+// list of runs takes 4 bytes per run, bitmap is 8KiB, switch when runs get bigger and
+// switch back only when they are twice as small, so that one value doesn't flip it back and forth
+const RUNS_TO_BITMAP: usize = WORDS * 2;
+const BITMAP_TO_RUNS: usize = WORDS;
+
+/// Set with the same contents as RBSet, but heavily fragmented areas are stored as bitmaps.
+///
+/// Values are grouped into blocks of 65536, every block holds either a list of ranges or a
+/// bitmap, whichever is smaller. Values must fit into i128.
+///
+/// Every block a range touches gets its own container, so a range spanning n blocks costs n
+/// containers, e.g. 2^20 for 0..=2^36. Sets made of long runs are better stored in RBSet.
+#[derive(Clone, Debug)]
+pub struct HybridRBSet<T> {
+    // sorted by block key
+    containers: Vec<(i128, Container)>,
+    _value: PhantomData<T>,
+}
+
+#[derive(Clone, Debug)]
+enum Container {
+    Runs(RBSet<u16>),
+    Bitmap(Bitmap),
+}
+
+#[derive(Clone, Debug)]
+struct Bitmap {
+    words: Box<[u64; WORDS]>,
+    len: usize,
+    // number of runs of ones, maintained on every change
+    runs: usize,
+}
+
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive + NumCast> HybridRBSet<T> {
+    pub fn new() -> Self {
+        HybridRBSet {
+            containers: Vec::new(),
+            _value: PhantomData,
+        }
+    }
+
+    pub fn insert(&mut self, value: T) {
+        self.insert_range(value, value);
+    }
+
+    pub fn remove(&mut self, value: &T) {
+        self.remove_range(*value, *value);
+    }
+
+    /// Insert all values in [start, end], does nothing if start > end.
+    pub fn insert_range(&mut self, start: T, end: T) {
+        if start > end {
+            return;
+        }
+        for (key, start, end) in split_blocks(start, end) {
+            let idx = match self.containers.binary_search_by_key(&key, |c| c.0) {
+                Ok(idx) => idx,
+                Err(idx) => {
+                    self.containers
+                        .insert(idx, (key, Container::Runs(RBSet::new())));
+                    idx
+                }
+            };
+            let container = &mut self.containers[idx].1;
+            match container {
+                Container::Runs(runs) => {
+                    runs.insert_range(start, end);
+                    if runs.ranges().len() > RUNS_TO_BITMAP {
+                        *container = Container::Bitmap(Bitmap::from_runs(runs));
+                    }
+                }
+                Container::Bitmap(bitmap) => {
+                    for value in start..=end {
+                        bitmap.insert(value);
+                    }
+                    // filling gaps merges runs, same as removing values in remove_range
+                    if bitmap.runs < BITMAP_TO_RUNS {
+                        *container = Container::Runs(bitmap.to_runs());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Remove all values in [start, end], does nothing if start > end.
+    ///
+    /// Only existing containers are visited, so this is cheap for large ranges.
+    pub fn remove_range(&mut self, start: T, end: T) {
+        if start > end {
+            return;
+        }
+        let (start_key, start_offset) = split(start);
+        let (end_key, end_offset) = split(end);
+        let first = self.containers.partition_point(|c| c.0 < start_key);
+        let last = self.containers.partition_point(|c| c.0 <= end_key);
+        for (key, container) in &mut self.containers[first..last] {
+            let start = if *key == start_key { start_offset } else { 0 };
+            let end = if *key == end_key {
+                end_offset
+            } else {
+                u16::MAX
+            };
+            if start == 0 && end == u16::MAX {
+                *container = Container::Runs(RBSet::new());
+                continue;
+            }
+            match container {
+                Container::Runs(runs) => runs.remove_range(start, end),
+                Container::Bitmap(bitmap) => {
+                    for value in start..=end {
+                        bitmap.remove(value);
+                    }
+                    if bitmap.runs < BITMAP_TO_RUNS {
+                        *container = Container::Runs(bitmap.to_runs());
+                    }
+                }
+            }
+        }
+        let kept: Vec<(i128, Container)> = self
+            .containers
+            .drain(first..last)
+            .filter(|(_, container)| !container.is_empty())
+            .collect();
+        self.containers.splice(first..first, kept);
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        let (key, offset) = split(*value);
+        match self.containers.binary_search_by_key(&key, |c| c.0) {
+            Ok(idx) => match &self.containers[idx].1 {
+                Container::Runs(runs) => runs.contains(&offset),
+                Container::Bitmap(bitmap) => bitmap.contains(offset),
+            },
+            Err(_) => false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.containers.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.containers.iter().map(|(_, c)| c.len()).sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.containers.clear();
+    }
+
+    /// Ranges in the same form as [RBSet::ranges].
+    pub fn ranges(&self) -> Vec<(T, T)> {
+        let mut ranges: Vec<(i128, i128)> = Vec::new();
+        for (key, container) in &self.containers {
+            let base = key << BLOCK_BITS;
+            container.for_each_run(|start, end| {
+                let (start, end) = (base + start as i128, base + end as i128);
+                match ranges.last_mut() {
+                    // run continues from the previous block
+                    Some(last) if last.1 + 1 == start => last.1 = end,
+                    _ => ranges.push((start, end)),
+                }
+            });
+        }
+        ranges
+            .into_iter()
+            .map(|(start, end)| (T::from(start).unwrap(), T::from(end).unwrap()))
+            .collect()
+    }
+
+    pub fn to_set(&self) -> RBSet<T> {
+        RBSet::from_sorted_ranges_unchecked(self.ranges())
+    }
+}
+
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive + NumCast> From<&RBSet<T>>
+    for HybridRBSet<T>
+{
+    fn from(set: &RBSet<T>) -> Self {
+        let mut hybrid = HybridRBSet::new();
+        for (start, end) in set.ranges() {
+            hybrid.insert_range(*start, *end);
+        }
+        hybrid
+    }
+}
+
+impl<T> Default for HybridRBSet<T> {
+    fn default() -> Self {
+        HybridRBSet {
+            containers: Vec::new(),
+            _value: PhantomData,
+        }
+    }
+}
+
+impl Container {
+    fn is_empty(&self) -> bool {
+        match self {
+            Container::Runs(runs) => runs.is_empty(),
+            Container::Bitmap(bitmap) => bitmap.len == 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Container::Runs(runs) => runs
+                .ranges()
+                .iter()
+                .map(|(start, end)| (end - start) as usize + 1)
+                .sum(),
+            Container::Bitmap(bitmap) => bitmap.len,
+        }
+    }
+
+    fn for_each_run(&self, mut f: impl FnMut(u16, u16)) {
+        match self {
+            Container::Runs(runs) => {
+                for (start, end) in runs.ranges() {
+                    f(*start, *end);
+                }
+            }
+            Container::Bitmap(bitmap) => {
+                word_runs(&bitmap.words[..], |start, end| f(start as u16, end as u16))
+            }
+        }
+    }
+}
+
+impl Bitmap {
+    fn from_runs(runs: &RBSet<u16>) -> Self {
+        let mut bitmap = Bitmap {
+            words: Box::new([0; WORDS]),
+            len: 0,
+            runs: runs.ranges().len(),
+        };
+        for (start, end) in runs.ranges() {
+            for value in *start..=*end {
+                bitmap.words[value as usize / 64] |= 1 << (value % 64);
+            }
+            bitmap.len += (end - start) as usize + 1;
+        }
+        bitmap
+    }
+
+    fn to_runs(&self) -> RBSet<u16> {
+        let mut ranges = Vec::with_capacity(self.runs);
+        word_runs(&self.words[..], |start, end| {
+            ranges.push((start as u16, end as u16))
+        });
+        RBSet::from_sorted_ranges_unchecked(ranges)
+    }
+
+    fn contains(&self, value: u16) -> bool {
+        self.words[value as usize / 64] & (1 << (value % 64)) != 0
+    }
+
+    fn insert(&mut self, value: u16) {
+        if self.contains(value) {
+            return;
+        }
+        let (left, right) = self.neighbours(value);
+        // new run, or extends one, or joins two
+        self.runs = self.runs + 1 - left as usize - right as usize;
+        self.words[value as usize / 64] |= 1 << (value % 64);
+        self.len += 1;
+    }
+
+    fn remove(&mut self, value: u16) {
+        if !self.contains(value) {
+            return;
+        }
+        let (left, right) = self.neighbours(value);
+        self.runs = self.runs + left as usize + right as usize - 1;
+        self.words[value as usize / 64] &= !(1 << (value % 64));
+        self.len -= 1;
+    }
+
+    fn neighbours(&self, value: u16) -> (bool, bool) {
+        let left = value > 0 && self.contains(value - 1);
+        let right = value < u16::MAX && self.contains(value + 1);
+        (left, right)
+    }
+}
+
+fn split<T: ToPrimitive>(value: T) -> (i128, u16) {
+    let value = value
+        .to_i128()
+        .expect("HybridRBSet values must fit into i128");
+    (value >> BLOCK_BITS, (value & BLOCK_MASK) as u16)
+}
+
+// split [start, end] at block boundaries
+fn split_blocks<T: ToPrimitive>(start: T, end: T) -> impl Iterator<Item = (i128, u16, u16)> {
+    let (start_key, start_offset) = split(start);
+    let (end_key, end_offset) = split(end);
+    (start_key..=end_key).map(move |key| {
+        let start = if key == start_key { start_offset } else { 0 };
+        let end = if key == end_key { end_offset } else { u16::MAX };
+        (key, start, end)
+    })
+}
+
+// calls f(start, end) for every run of set bits, bit i of words[j] is position j * 64 + i
+pub(crate) fn word_runs(words: &[u64], mut f: impl FnMut(u64, u64)) {
+    let mut run_start = None;
+    for (idx, word) in words.iter().enumerate() {
+        let base = idx as u64 * 64;
+        let mut pos = 0;
+        while pos < 64 {
+            let rest = word >> pos;
+            match run_start {
+                None => {
+                    if rest == 0 {
+                        break;
+                    }
+                    pos += rest.trailing_zeros();
+                    run_start = Some(base + pos as u64);
+                }
+                Some(start) => {
+                    // bits shifted in from the top are zeros, so this stops at bit 63
+                    pos += rest.trailing_ones();
+                    if pos < 64 {
+                        f(start, base + pos as u64 - 1);
+                        run_start = None;
+                    }
+                }
+            }
+        }
+    }
+    if let Some(start) = run_start {
+        f(start, words.len() as u64 * 64 - 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switches_representation() {
+        let mut set = HybridRBSet::<u32>::new();
+        for x in (0..68_000).step_by(2) {
+            set.insert(x);
+        }
+        let mut expected = RBSet::from_sorted_iter((0..68_000).step_by(2));
+        assert!(matches!(set.containers[0].1, Container::Bitmap(_)));
+        assert!(matches!(set.containers[1].1, Container::Runs(_)));
+        assert_eq!(set.len(), 34_000);
+        assert!(set.contains(&65_534));
+        assert!(!set.contains(&65_535));
+        assert_eq!(set.ranges(), expected.ranges());
+
+        set.remove_range(0, 64_000);
+        expected.remove_range(0, 64_000);
+        assert!(matches!(set.containers[0].1, Container::Runs(_)));
+        assert_eq!(set.ranges(), expected.ranges());
+    }
+
+    #[test]
+    fn filled_bitmap_switches_back() {
+        let mut set = HybridRBSet::<u32>::new();
+        for x in (0..65_536).step_by(2) {
+            set.insert(x);
+        }
+        assert!(matches!(set.containers[0].1, Container::Bitmap(_)));
+        set.insert_range(0, 65_535);
+        assert!(matches!(set.containers[0].1, Container::Runs(_)));
+        assert_eq!(set.ranges(), vec![(0, 65_535)]);
+        assert_eq!(set.len(), 65_536);
+    }
+
+    #[test]
+    fn ranges_across_blocks() {
+        let mut set = HybridRBSet::<i64>::new();
+        set.insert_range(-70_000, 140_000);
+        set.remove(&0);
+        assert_eq!(set.ranges(), vec![(-70_000, -1), (1, 140_000)]);
+        assert_eq!(set.len(), 210_000);
+        set.remove_range(-100_000, 200_000);
+        assert!(set.is_empty());
+        assert!(set.containers.is_empty());
+    }
+
+    #[test]
+    fn remove_huge_range() {
+        let mut set = HybridRBSet::<u64>::new();
+        set.insert(1 << 40);
+        set.insert_range(0, 70_000);
+        set.remove_range(10, u64::MAX);
+        assert_eq!(set.ranges(), vec![(0, 9)]);
+        set.remove_range(0, u64::MAX);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn word_runs() {
+        let mut runs = Vec::new();
+        super::word_runs(&[0b1110_0001, u64::MAX << 63, u64::MAX, 1], |start, end| {
+            runs.push((start, end))
+        });
+        assert_eq!(runs, vec![(0, 0), (5, 7), (127, 192)]);
+    }
+}
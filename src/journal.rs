@@ -0,0 +1,174 @@
+use std::ops::{AddAssign, Deref, SubAssign};
+
+use num_traits::{Num, ToPrimitive};
+
+use crate::{RBSet, RangesEdit};
+
+/// RBSet that can record mutations made inside transactions and roll them back.
+///
+/// Only the edited ranges are recorded, not the whole set. Transactions can be nested,
+/// mutations made outside of any transaction are not recorded.
+#[derive(Clone, Debug)]
+pub struct JournaledRBSet<T> {
+    set: RBSet<T>,
+    journal: Vec<JournalEntry<T>>,
+    // journal length at the start of each open transaction
+    txn_starts: Vec<usize>,
+}
+
+#[derive(Clone, Debug)]
+struct JournalEntry<T> {
+    // position of the edit in ranges
+    at: usize,
+    // ranges that were replaced
+    old: Vec<(T, T)>,
+    // number of ranges they were replaced with
+    new_len: usize,
+}
+
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> JournaledRBSet<T> {
+    pub fn new(set: RBSet<T>) -> Self {
+        JournaledRBSet {
+            set,
+            journal: Vec::new(),
+            txn_starts: Vec::new(),
+        }
+    }
+
+    pub fn insert(&mut self, value: T) {
+        self.insert_range(value, value);
+    }
+
+    pub fn remove(&mut self, value: &T) {
+        self.remove_range(*value, *value);
+    }
+
+    pub fn insert_range(&mut self, start: T, end: T) {
+        if let Some(edit) = self.set.insert_range_edit(start, end, None) {
+            self.apply_edit(edit);
+        }
+    }
+
+    /// Returns how many values were not in the set before, like [RBSet::insert_range_counted].
+    pub fn insert_range_counted(&mut self, start: T, end: T) -> u128 {
+        let Some(edit) = self.set.insert_range_edit(start, end, None) else {
+            return 0;
+        };
+        let added = self.set.insert_edit_added(&edit);
+        self.apply_edit(edit);
+        added
+    }
+
+    pub fn remove_range(&mut self, start: T, end: T) {
+        if let Some(edit) = self.set.remove_range_edit(start, end, None) {
+            self.apply_edit(edit);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        if !self.set.is_empty() {
+            self.apply_edit(RangesEdit {
+                window: 0..self.set.ranges.len(),
+                replacement: [None, None],
+            });
+        }
+    }
+
+    pub fn begin_txn(&mut self) {
+        self.txn_starts.push(self.journal.len());
+    }
+
+    /// Keep changes made in the innermost transaction, they can still be rolled back
+    /// by the outer one. Returns false if there is no open transaction.
+    pub fn commit(&mut self) -> bool {
+        if self.txn_starts.pop().is_none() {
+            return false;
+        }
+        if self.txn_starts.is_empty() {
+            self.journal.clear();
+        }
+        true
+    }
+
+    /// Undo all changes made in the innermost transaction. Returns false if there is no
+    /// open transaction.
+    pub fn rollback(&mut self) -> bool {
+        let Some(txn_start) = self.txn_starts.pop() else {
+            return false;
+        };
+        for entry in self.journal.drain(txn_start..).rev() {
+            self.set
+                .ranges
+                .splice(entry.at..entry.at + entry.new_len, entry.old);
+        }
+        true
+    }
+
+    pub fn in_txn(&self) -> bool {
+        !self.txn_starts.is_empty()
+    }
+
+    pub fn as_set(&self) -> &RBSet<T> {
+        &self.set
+    }
+
+    /// Drops any open transactions, keeping all changes.
+    pub fn into_inner(self) -> RBSet<T> {
+        self.set
+    }
+
+    fn apply_edit(&mut self, edit: RangesEdit<T>) {
+        let at = edit.window.start;
+        let new_len = edit.replacement.iter().flatten().count();
+        let old = self.set.apply_edit(edit);
+        if self.in_txn() {
+            self.journal.push(JournalEntry { at, old, new_len });
+        }
+    }
+}
+
+impl<T> Deref for JournaledRBSet<T> {
+    type Target = RBSet<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rollback() {
+        let mut set = JournaledRBSet::new(RBSet::try_from(vec![(0u32, 10), (20, 30)]).unwrap());
+        set.begin_txn();
+        assert_eq!(set.insert_range_counted(5, 25), 9);
+        set.remove(&3);
+        set.insert(40);
+        assert_eq!(set.ranges(), &[(0, 2), (4, 30), (40, 40)]);
+        assert!(set.rollback());
+        assert_eq!(set.ranges(), &[(0, 10), (20, 30)]);
+        assert!(!set.rollback());
+    }
+
+    #[test]
+    fn nested_txn() {
+        let mut set = JournaledRBSet::new(RBSet::<i16>::new());
+        set.insert(1);
+        set.begin_txn();
+        set.insert(2);
+        set.begin_txn();
+        set.clear();
+        assert!(set.is_empty());
+        assert!(set.rollback());
+        assert_eq!(set.ranges(), &[(1, 2)]);
+        set.begin_txn();
+        set.remove_range(-5, 1);
+        assert!(set.commit());
+        assert_eq!(set.ranges(), &[(2, 2)]);
+        assert!(set.rollback());
+        assert_eq!(set.ranges(), &[(1, 1)]);
+        assert!(!set.in_txn());
+    }
+}
@@ -1,22 +1,234 @@
 use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
     fmt::Display,
-    ops::{AddAssign, SubAssign},
+    ops::{AddAssign, Bound, Range, RangeBounds, RangeFrom, RangeInclusive, SubAssign},
 };
 
-use num_traits::{Num, ToPrimitive};
+use num_traits::{Bounded, Num, NumCast, ToPrimitive};
 
-#[derive(Clone, serde::Serialize, serde::Deserialize, Debug)]
+mod batch;
+mod bitset;
+mod btree;
+mod budgeted;
+mod bytes;
+mod concurrent;
+mod cursor;
+mod expiring;
+mod expr;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod frozen;
+mod hybrid;
+mod journal;
+mod macros;
+mod merge;
+#[cfg(test)]
+mod proptests;
+#[cfg(feature = "serde")]
+pub mod serde_formats;
+mod sliding;
+mod strided;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod view;
+
+pub use batch::Op;
+pub use btree::BTreeRBSet;
+pub use budgeted::BudgetedRBSet;
+pub use bytes::RBSetBytes;
+pub use concurrent::ConcurrentRBSet;
+pub use cursor::RBSetCursor;
+pub use expiring::ExpiringRBSet;
+pub use expr::{Difference, Intersection, SetExpr, Union};
+pub use frozen::FrozenRBSet;
+pub use hybrid::HybridRBSet;
+pub use journal::JournaledRBSet;
+pub use macros::RangeItem;
+pub use merge::{DifferenceRanges, IntersectionRanges, SliceRanges, UnionRanges};
+pub use sliding::SlidingRBSet;
+pub use strided::StridedRBSet;
+pub use view::{RBSetRef, RBSetView, RBSetWindow};
+
+/// Sets compare lexicographically by their ranges, ranges are (start, end) tuples. As ranges are
+/// always coalesced, equal sets always compare equal.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RBSet<T> {
     // [start, end]
     ranges: Vec<(T, T)>,
 }
 
 impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> RBSet<T> {
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         RBSet { ranges: Vec::new() }
     }
 
+    /// Borrow the ranges without copying.
+    pub fn as_view(&self) -> RBSetView<'_, T> {
+        RBSetView::from_sorted_ranges_unchecked(&self.ranges)
+    }
+
+    pub fn union(&self, other: &RBSet<T>) -> RBSet<T> {
+        self.as_view().union(other)
+    }
+
+    pub fn intersection(&self, other: &RBSet<T>) -> RBSet<T> {
+        self.as_view().intersection(other)
+    }
+
+    /// Values in self, but not in other.
+    pub fn difference(&self, other: &RBSet<T>) -> RBSet<T> {
+        self.as_view().difference(other)
+    }
+
+    /// Union of any number of sets in one k-way merge over their ranges.
+    pub fn union_all(sets: &[&RBSet<T>]) -> RBSet<T> {
+        let mut heap: BinaryHeap<NextRange<T>> = sets
+            .iter()
+            .enumerate()
+            .filter_map(|(set, s)| {
+                s.ranges.first().map(|r| NextRange {
+                    start: r.0,
+                    set,
+                    idx: 0,
+                })
+            })
+            .collect();
+        let mut ranges: Vec<(T, T)> = Vec::new();
+        while let Some(NextRange { set, idx, .. }) = heap.pop() {
+            let next = sets[set].ranges[idx];
+            if let Some(r) = sets[set].ranges.get(idx + 1) {
+                heap.push(NextRange {
+                    start: r.0,
+                    set,
+                    idx: idx + 1,
+                });
+            }
+            match ranges.last_mut() {
+                // next.0 - 1 is only evaluated when next.0 > last.1
+                Some(last) if next.0 <= last.1 || next.0 - T::one() == last.1 => {
+                    if next.1 > last.1 {
+                        last.1 = next.1;
+                    }
+                }
+                _ => ranges.push(next),
+            }
+        }
+        RBSet { ranges }
+    }
+
+    /// Number of values in the union, without building it.
+    pub fn union_len(&self, other: &RBSet<T>) -> usize {
+        to_len(count_values(self.union_iter(other)))
+    }
+
+    /// Number of values in the intersection, without building it.
+    pub fn intersection_len(&self, other: &RBSet<T>) -> usize {
+        to_len(count_values(self.intersection_iter(other)))
+    }
+
+    /// Number of values in self, but not in other, without building the difference.
+    pub fn difference_len(&self, other: &RBSet<T>) -> usize {
+        to_len(count_values(self.difference_iter(other)))
+    }
+
+    /// Size of the intersection divided by size of the union, 1.0 if both sets are empty.
+    pub fn jaccard(&self, other: &RBSet<T>) -> f64 {
+        // counted directly instead of len + len - common, which overflows u128 for big sets
+        let common = self.intersection_len_u128(other);
+        let union = count_values(self.union_iter(other));
+        if union == 0 {
+            1.0
+        } else {
+            common as f64 / union as f64
+        }
+    }
+
+    /// Size of the intersection divided by size of the smaller set, 1.0 if either set is empty.
+    pub fn overlap_coefficient(&self, other: &RBSet<T>) -> f64 {
+        let smaller = self.len_u128().min(other.len_u128());
+        if smaller == 0 {
+            1.0
+        } else {
+            self.intersection_len_u128(other) as f64 / smaller as f64
+        }
+    }
+
+    // lengths that don't overflow usize when the set covers all of a 64 bit type, they
+    // saturate when it covers all of u128 or i128
+    fn len_u128(&self) -> u128 {
+        count_values(self.ranges.iter().copied())
+    }
+
+    fn intersection_len_u128(&self, other: &RBSet<T>) -> u128 {
+        count_values(self.intersection_iter(other))
+    }
+
+    /// Ranges of the union, without allocating a new set.
+    pub fn union_iter<'a>(
+        &'a self,
+        other: &'a RBSet<T>,
+    ) -> UnionRanges<SliceRanges<'a, T>, SliceRanges<'a, T>> {
+        UnionRanges::new(self.ranges.iter().copied(), other.ranges.iter().copied())
+    }
+
+    pub fn intersection_iter<'a>(
+        &'a self,
+        other: &'a RBSet<T>,
+    ) -> IntersectionRanges<SliceRanges<'a, T>, SliceRanges<'a, T>> {
+        IntersectionRanges::new(self.ranges.iter().copied(), other.ranges.iter().copied())
+    }
+
+    pub fn difference_iter<'a>(
+        &'a self,
+        other: &'a RBSet<T>,
+    ) -> DifferenceRanges<SliceRanges<'a, T>, SliceRanges<'a, T>> {
+        DifferenceRanges::new(self.ranges.iter().copied(), other.ranges.iter().copied())
+    }
+
+    /// Build a set directly from `ranges` without any checks.
+    ///
+    /// Caller must guarantee that every range has `start <= end`, ranges are sorted
+    /// and that no two ranges overlap or touch (i.e. they are already coalesced).
+    pub fn from_sorted_ranges_unchecked(ranges: Vec<(T, T)>) -> Self {
+        RBSet { ranges }
+    }
+
+    /// Build a set from values in ascending order in a single pass, duplicates are allowed.
+    ///
+    /// Panics if values are not in ascending order.
+    pub fn from_sorted_iter(iter: impl Iterator<Item = T>) -> Self {
+        let mut ranges: Vec<(T, T)> = Vec::new();
+        for value in iter {
+            match ranges.last_mut() {
+                Some((_, end)) => {
+                    // end is the previous value
+                    assert!(value >= *end, "from_sorted_iter: input is not sorted");
+                    if value == *end {
+                        continue;
+                    } else if value - T::one() == *end {
+                        *end = value;
+                    } else {
+                        ranges.push((value, value));
+                    }
+                }
+                None => ranges.push((value, value)),
+            }
+        }
+        RBSet { ranges }
+    }
+
+    /// Build a set from values in any order, `values` is sorted in place.
+    pub fn from_unsorted(values: &mut [T]) -> Self {
+        values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        Self::from_sorted_iter(values.iter().copied())
+    }
+
     pub fn insert(&mut self, value: T) {
+        if self.try_push_max(value) {
+            return;
+        }
         if self.ranges.is_empty() {
             self.ranges.push((value, value));
         } else {
@@ -65,6 +277,36 @@ impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> RBSet<T>
         }
     }
 
+    /// Insert a value that is not less than the current maximum in O(1).
+    ///
+    /// Panics if value is less than the maximum, [RBSet::insert] takes the same fast path
+    /// automatically.
+    pub fn push_max(&mut self, value: T) {
+        assert!(
+            self.try_push_max(value),
+            "push_max: value is less than the maximum"
+        );
+    }
+
+    // extend or append the last range if value is >= than the maximum
+    fn try_push_max(&mut self, value: T) -> bool {
+        match self.ranges.last_mut() {
+            Some((_, end)) if value > *end => {
+                if value - T::one() == *end {
+                    *end = value;
+                } else {
+                    self.ranges.push((value, value));
+                }
+                true
+            }
+            Some((_, end)) => value == *end,
+            None => {
+                self.ranges.push((value, value));
+                true
+            }
+        }
+    }
+
     pub fn remove(&mut self, value: &T) {
         let mut add_range = None;
         for (idx, (start, end)) in self.ranges.iter_mut().enumerate() {
@@ -92,6 +334,156 @@ impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> RBSet<T>
         }
     }
 
+    /// Insert all values in [start, end], does nothing if start > end.
+    pub fn insert_range(&mut self, start: T, end: T) {
+        if let Some(edit) = self.insert_range_edit(start, end, None) {
+            self.apply_edit(edit);
+        }
+    }
+
+    /// Same as [RBSet::insert_range], but returns how many of the values were not in the set
+    /// before.
+    ///
+    /// The count saturates at u128::MAX, which only happens when the whole u128 or i128
+    /// domain is inserted into an empty set.
+    pub fn insert_range_counted(&mut self, start: T, end: T) -> u128 {
+        match self.insert_range_edit(start, end, None) {
+            Some(edit) => {
+                let added = self.insert_edit_added(&edit);
+                self.apply_edit(edit);
+                added
+            }
+            None => 0,
+        }
+    }
+
+    /// Remove all values in [start, end], does nothing if start > end.
+    pub fn remove_range(&mut self, start: T, end: T) {
+        if let Some(edit) = self.remove_range_edit(start, end, None) {
+            self.apply_edit(edit);
+        }
+    }
+
+    /// Remove all values <= value.
+    pub fn remove_up_to(&mut self, value: T) {
+        let whole = self.ranges.partition_point(|range| range.1 <= value);
+        self.ranges.drain(..whole);
+        if let Some(first) = self.ranges.first_mut() {
+            if first.0 <= value {
+                // first.1 > value, so this can't overflow
+                first.0 = value + T::one();
+            }
+        }
+    }
+
+    // hint is a position in ranges close to the edit, searching is done around it instead
+    // of bisecting the whole list
+    pub(crate) fn insert_range_edit(
+        &self,
+        start: T,
+        end: T,
+        hint: Option<usize>,
+    ) -> Option<RangesEdit<T>> {
+        if start > end {
+            return None;
+        }
+        // first range that overlaps or touches [start, end] from the left
+        let first = partition_point_near(&self.ranges, hint, |r| {
+            !(r.1 >= start || start - T::one() == r.1)
+        });
+        // one past the last range that overlaps or touches [start, end] from the right
+        let last =
+            partition_point_near(&self.ranges, hint, |r| r.0 <= end || r.0 - T::one() == end);
+        let mut merged = (start, end);
+        if first != last {
+            if self.ranges[first].0 < start {
+                merged.0 = self.ranges[first].0;
+            }
+            if self.ranges[last - 1].1 > end {
+                merged.1 = self.ranges[last - 1].1;
+            }
+        }
+        Some(RangesEdit {
+            window: first..last,
+            replacement: [Some(merged), None],
+        })
+    }
+
+    pub(crate) fn remove_range_edit(
+        &self,
+        start: T,
+        end: T,
+        hint: Option<usize>,
+    ) -> Option<RangesEdit<T>> {
+        if start > end {
+            return None;
+        }
+        let first = partition_point_near(&self.ranges, hint, |r| r.1 < start);
+        let last = partition_point_near(&self.ranges, hint, |r| r.0 <= end);
+        if first == last {
+            return None;
+        }
+        let mut replacement = [None, None];
+        if self.ranges[first].0 < start {
+            replacement[0] = Some((self.ranges[first].0, start - T::one()));
+        }
+        if self.ranges[last - 1].1 > end {
+            replacement[1] = Some((end + T::one(), self.ranges[last - 1].1));
+        }
+        Some(RangesEdit {
+            window: first..last,
+            replacement,
+        })
+    }
+
+    // returns ranges that were replaced
+    pub(crate) fn apply_edit(&mut self, edit: RangesEdit<T>) -> Vec<(T, T)> {
+        self.ranges
+            .splice(edit.window, edit.replacement.into_iter().flatten())
+            .collect()
+    }
+
+    // number of values an edit from insert_range_edit adds, every range it replaces is inside
+    // the merged one, saturates at u128::MAX
+    pub(crate) fn insert_edit_added(&self, edit: &RangesEdit<T>) -> u128 {
+        let merged = edit.replacement[0].expect("insert edit has a merged range");
+        let merged = distance(merged.0, merged.1);
+        let replaced = &self.ranges[edit.window.clone()];
+        if replaced.is_empty() {
+            return merged.saturating_add(1);
+        }
+        // number of replaced values minus one, they are all inside merged so this doesn't
+        // overflow even if merged covers all of u128
+        let before: u128 = replaced
+            .iter()
+            .map(|(start, end)| distance(*start, *end))
+            .sum::<u128>()
+            + (replaced.len() - 1) as u128;
+        merged - before
+    }
+
+    /// Ranges to insert and remove to turn self into other.
+    pub fn diff(&self, other: &RBSet<T>) -> RBSetDelta<T> {
+        RBSetDelta {
+            insert: other.difference_iter(self).collect(),
+            remove: self.difference_iter(other).collect(),
+        }
+    }
+
+    pub fn apply(&mut self, delta: &RBSetDelta<T>) {
+        for (start, end) in &delta.remove {
+            self.remove_range(*start, *end);
+        }
+        for (start, end) in &delta.insert {
+            self.insert_range(*start, *end);
+        }
+    }
+
+    /// Keep only stored ranges for which f returns true.
+    pub fn retain_ranges(&mut self, mut f: impl FnMut(&RangeInclusive<T>) -> bool) {
+        self.ranges.retain(|&(start, end)| f(&(start..=end)));
+    }
+
     pub fn clear(&mut self) {
         self.ranges.clear();
     }
@@ -109,29 +501,678 @@ impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> RBSet<T>
         false
     }
 
-    pub fn iter(&self) -> RBSetIter<T> {
+    /// True if every value is in the set, values are sorted and checked in one sweep.
+    pub fn contains_all(&self, values: impl IntoIterator<Item = T>) -> bool {
+        let mut found = true;
+        self.sweep(values, |contained| {
+            found = contained;
+            contained
+        });
+        found
+    }
+
+    /// True if at least one value is in the set, values are sorted and checked in one sweep.
+    pub fn contains_any(&self, values: impl IntoIterator<Item = T>) -> bool {
+        let mut found = false;
+        self.sweep(values, |contained| {
+            found = contained;
+            !contained
+        });
+        found
+    }
+
+    // calls f with membership of each value in ascending order, until f returns false
+    fn sweep(&self, values: impl IntoIterator<Item = T>, mut f: impl FnMut(bool) -> bool) {
+        let mut values: Vec<T> = values.into_iter().collect();
+        values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        let mut idx = 0;
+        for value in values {
+            while idx < self.ranges.len() && self.ranges[idx].1 < value {
+                idx += 1;
+            }
+            let contained = idx < self.ranges.len() && self.ranges[idx].0 <= value;
+            if !f(contained) {
+                return;
+            }
+        }
+    }
+
+    /// Set is one range without holes, false if the set is empty.
+    pub fn is_contiguous(&self) -> bool {
+        self.ranges.len() == 1
+    }
+
+    /// The only range if the set is contiguous.
+    pub fn as_single_range(&self) -> Option<RangeInclusive<T>> {
+        match self.ranges[..] {
+            [(start, end)] => Some(start..=end),
+            _ => None,
+        }
+    }
+
+    /// Contained value nearest to value, lower one if two are equally close.
+    pub fn closest(&self, value: &T) -> Option<T> {
+        self.closest_by(value, Tie::Lower)
+    }
+
+    /// Contained value nearest to value, tie decides which one to pick if two are equally close.
+    pub fn closest_by(&self, value: &T, tie: Tie) -> Option<T> {
+        let idx = self.ranges.partition_point(|r| r.1 < *value);
+        let above = match self.ranges.get(idx) {
+            Some(range) if range.0 <= *value => return Some(*value),
+            Some(range) => Some(range.0),
+            None => None,
+        };
+        let below = idx.checked_sub(1).map(|idx| self.ranges[idx].1);
+        match (below, above) {
+            (Some(below), Some(above)) => {
+                match distance(below, *value).cmp(&distance(*value, above)) {
+                    Ordering::Less => Some(below),
+                    Ordering::Greater => Some(above),
+                    Ordering::Equal => match tie {
+                        Tie::Lower => Some(below),
+                        Tie::Higher => Some(above),
+                    },
+                }
+            }
+            (below, above) => below.or(above),
+        }
+    }
+
+    /// Stored range that contains value.
+    pub fn get_range(&self, value: &T) -> Option<RangeInclusive<T>> {
+        let idx = self.ranges.partition_point(|r| r.1 < *value);
+        match self.ranges.get(idx) {
+            Some(&(start, end)) if start <= *value => Some(start..=end),
+            _ => None,
+        }
+    }
+
+    pub fn overlaps(&self, range: RangeInclusive<T>) -> bool {
+        self.overlapping_ranges(range).next().is_some()
+    }
+
+    /// Stored ranges that have at least one value in common with range.
+    pub fn overlapping_ranges(
+        &self,
+        range: RangeInclusive<T>,
+    ) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        let (start, end) = range.into_inner();
+        let overlapping = if start > end {
+            &[]
+        } else {
+            let first = self.ranges.partition_point(|r| r.1 < start);
+            let last = self.ranges.partition_point(|r| r.0 <= end);
+            &self.ranges[first..last.max(first)]
+        };
+        overlapping.iter().map(|&(start, end)| start..=end)
+    }
+
+    /// Fragmentation metrics, None for an empty set.
+    pub fn stats(&self) -> Option<RBSetStats> {
+        let first = self.ranges.first()?.0;
+        let last = self.ranges.last()?.1;
+        let mut largest_range_len = 0;
+        let mut smallest_range_len = u128::MAX;
+        for (start, end) in &self.ranges {
+            let range_len = distance(*start, *end).saturating_add(1);
+            largest_range_len = largest_range_len.max(range_len);
+            smallest_range_len = smallest_range_len.min(range_len);
+        }
+        let len = self.len_u128();
+        let span = distance(first, last);
+        Some(RBSetStats {
+            range_count: self.ranges.len(),
+            largest_range_len,
+            smallest_range_len,
+            span,
+            density: len as f64 / (span as f64 + 1.0),
+        })
+    }
+
+    /// Cursor positioned at the range containing value, or at the first range after it.
+    pub fn cursor_at(&mut self, value: &T) -> RBSetCursor<'_, T> {
+        let pos = self.ranges.partition_point(|r| r.1 < *value);
+        RBSetCursor::new(self, pos)
+    }
+
+    pub fn iter(&self) -> RBSetIter<'_, T> {
         RBSetIter {
-            set: self,
+            ranges: &self.ranges,
             pos: 0,
             last_yielded: None,
         }
     }
 
+    /// Values >= start in ascending order.
+    pub fn iter_from(&self, start: &T) -> RBSetIter<'_, T> {
+        let pos = self.ranges.partition_point(|r| r.1 < *start);
+        let last_yielded = match self.ranges.get(pos) {
+            // start - 1 can't overflow, it's >= range start
+            Some(range) if range.0 < *start => Some(*start - T::one()),
+            _ => None,
+        };
+        RBSetIter {
+            ranges: &self.ranges,
+            pos,
+            last_yielded,
+        }
+    }
+
+    /// Continue iteration from a token taken from an earlier iterator.
+    pub fn iter_resume(&self, token: Resume<T>) -> RBSetIter<'_, T> {
+        self.iter_from(&token.next)
+    }
+
+    /// Values in ascending order in batches of `chunk`, the last batch may be shorter.
+    ///
+    /// Panics if chunk is 0.
+    pub fn iter_chunks(&self, chunk: usize) -> impl Iterator<Item = Vec<T>> + '_ {
+        assert!(chunk != 0, "chunk size must be non-zero");
+        let mut iter = self.iter();
+        core::iter::from_fn(move || {
+            let batch: Vec<T> = iter.by_ref().take(chunk).collect();
+            (!batch.is_empty()).then_some(batch)
+        })
+    }
+
     pub fn len(&self) -> usize {
-        // self.iter().count()
-        let mut count = 0;
+        len_of(self.ranges.iter().copied())
+    }
+
+    /// Smallest value.
+    pub fn first(&self) -> Option<T> {
+        self.ranges.first().map(|range| range.0)
+    }
+
+    /// Largest value.
+    pub fn last(&self) -> Option<T> {
+        self.ranges.last().map(|range| range.1)
+    }
+
+    /// Sum of all values, computed per range.
+    ///
+    /// Panics if it doesn't fit into i128.
+    pub fn sum(&self) -> i128 {
+        self.checked_sum().expect("sum overflows i128")
+    }
+
+    /// Sum of all values, None if it or any value doesn't fit into i128.
+    pub fn checked_sum(&self) -> Option<i128> {
+        let mut sum: i128 = 0;
         for (start, end) in &self.ranges {
-            let diff = *end - *start + T::one();
-            count += diff.to_usize().unwrap();
+            let (start, end) = (start.to_i128()?, end.to_i128()?);
+            let pair = start.checked_add(end)?;
+            let count = i128::try_from(distance(start, end)).ok()?.checked_add(1)?;
+            // of pair and count exactly one is even, halve it before multiplying
+            let range_sum = if count % 2 == 0 {
+                pair.checked_mul(count / 2)?
+            } else {
+                (pair / 2).checked_mul(count)?
+            };
+            sum = sum.checked_add(range_sum)?;
         }
-        count
+        Some(sum)
+    }
+
+    /// Average value, None if the set is empty.
+    pub fn mean(&self) -> Option<f64> {
+        let mut weighted = 0.0;
+        let mut count = 0.0;
+        for (start, end) in &self.ranges {
+            let range_count = distance(*start, *end) as f64 + 1.0;
+            let mid = (start.to_f64()? + end.to_f64()?) / 2.0;
+            weighted += mid * range_count;
+            count += range_count;
+        }
+        (count > 0.0).then(|| weighted / count)
     }
 
     pub fn ranges(&self) -> &[(T, T)] {
         &self.ranges
     }
+
+    /// Ranges by value, can be reversed with `.rev()`.
+    pub fn iter_ranges(&self) -> SliceRanges<'_, T> {
+        self.ranges.iter().copied()
+    }
+
+    /// Up to n ranges with the largest values, in ascending order.
+    pub fn last_ranges(&self, n: usize) -> &[(T, T)] {
+        &self.ranges[self.ranges.len().saturating_sub(n)..]
+    }
+
+    /// Merge ranges separated by at most gap missing values, the values in between are added.
+    ///
+    /// Panics if gap is negative.
+    pub fn coalesce_with_tolerance(&mut self, gap: T) {
+        assert!(gap >= T::zero(), "gap must be >= 0");
+        let gap = distance(T::zero(), gap);
+        let mut write = 0;
+        for read in 1..self.ranges.len() {
+            let next = self.ranges[read];
+            let last = &mut self.ranges[write];
+            // ranges are disjoint and not adjacent, so at least one value is missing
+            if distance(last.1, next.0) - 1 <= gap {
+                last.1 = next.1;
+            } else {
+                write += 1;
+                self.ranges[write] = next;
+            }
+        }
+        self.ranges.truncate(write + 1);
+    }
+
+    // merge overlapping or adjacent ranges, ranges must be sorted by start
+    fn coalesce(&mut self) {
+        let mut write = 0;
+        for read in 1..self.ranges.len() {
+            let (start, end) = self.ranges[read];
+            let last = &mut self.ranges[write];
+            // start - 1 is only evaluated when start > last.1, so it can't underflow
+            if start <= last.1 || start - T::one() == last.1 {
+                if end > last.1 {
+                    last.1 = end;
+                }
+            } else {
+                write += 1;
+                self.ranges[write] = (start, end);
+            }
+        }
+        self.ranges.truncate(write + 1);
+    }
 }
 
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive + Bounded> RBSet<T> {
+    /// Restrict to values within bounds, without copying.
+    pub fn view_range(&self, bounds: impl RangeBounds<T>) -> RBSetWindow<'_, T> {
+        self.as_view().view_range(bounds)
+    }
+
+    /// Ranges as half-open start..end.
+    ///
+    /// A range ending at T::MAX has no exclusive end, so it is returned separately as start..
+    pub fn ranges_exclusive(
+        &self,
+    ) -> (
+        impl DoubleEndedIterator<Item = Range<T>> + ExactSizeIterator + '_,
+        Option<RangeFrom<T>>,
+    ) {
+        let (bounded, tail) = match self.ranges.split_last() {
+            Some((last, rest)) if last.1 == T::max_value() => (rest, Some(last.0..)),
+            _ => (&self.ranges[..], None),
+        };
+        let bounded = bounded.iter().map(|&(start, end)| start..end + T::one());
+        (bounded, tail)
+    }
+
+    /// Start of the first run of `len` values that are not in the set, with start >= after
+    /// and start a multiple of align.
+    ///
+    /// Panics if len or align is not positive.
+    pub fn next_free_slot(&self, after: T, len: T, align: T) -> Option<T> {
+        assert!(len > T::zero(), "len must be > 0");
+        assert!(align > T::zero(), "align must be > 0");
+        let max = T::max_value();
+        let mut pos = after;
+        let mut idx = self.ranges.partition_point(|r| r.1 < after);
+        loop {
+            // pos is either in ranges[idx] or in the gap before it
+            let next = self.ranges.get(idx).copied();
+            if let Some((start, end)) = next {
+                if start <= pos {
+                    if end == max {
+                        return None;
+                    }
+                    pos = end + T::one();
+                    idx += 1;
+                    continue;
+                }
+            }
+            let gap_end = match next {
+                Some((start, _)) => start - T::one(),
+                None => max,
+            };
+            let mut rem = pos % align;
+            if rem < T::zero() {
+                rem += align;
+            }
+            let candidate = if rem == T::zero() {
+                Some(pos)
+            } else if pos <= max - (align - rem) {
+                Some(pos + (align - rem))
+            } else {
+                None
+            };
+            if let Some(candidate) = candidate {
+                if candidate <= gap_end && distance(candidate, gap_end) >= distance(T::one(), len) {
+                    return Some(candidate);
+                }
+            }
+            match next {
+                Some((_, end)) if end != max => {
+                    pos = end + T::one();
+                    idx += 1;
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive + NumCast> RBSet<T> {
+    /// Value at position index in ascending order, without iterating over the values.
+    pub fn select(&self, index: usize) -> Option<T> {
+        self.select_u128(index as u128)
+    }
+
+    // select for indices past usize::MAX, which sets over 64 bit types can have
+    fn select_u128(&self, mut index: u128) -> Option<T> {
+        for &(start, end) in &self.ranges {
+            let range_len = distance(start, end);
+            if index <= range_len {
+                return Some(offset(start, index));
+            }
+            index -= range_len + 1;
+        }
+        None
+    }
+
+    /// Uniformly random value, pick(max) must return a uniformly random index in 0..=max,
+    /// e.g. `set.sample_with(|max| rng.gen_range(0..=max))`. None if the set is empty.
+    ///
+    /// Indices are u128 and max is the last index rather than the length, so that sets
+    /// larger than usize::MAX, up to all of u128, can be sampled.
+    ///
+    /// # Panics
+    /// If pick returns an index past max.
+    pub fn sample_with(&self, pick: impl FnOnce(u128) -> u128) -> Option<T> {
+        if self.ranges.is_empty() {
+            return None;
+        }
+        // len - 1, summed so that it doesn't overflow even when the set has 2^128 values
+        let max = self
+            .ranges
+            .iter()
+            .map(|(start, end)| distance(*start, *end))
+            .sum::<u128>()
+            + (self.ranges.len() - 1) as u128;
+        Some(
+            self.select_u128(pick(max))
+                .expect("pick returned an index past max"),
+        )
+    }
+
+    /// Remove and return the n smallest values, or all of them if there are fewer.
+    pub fn take_smallest(&mut self, n: usize) -> RBSet<T> {
+        let mut left = n as u128;
+        let mut whole = 0;
+        for &(start, end) in &self.ranges {
+            let range_len = distance(start, end);
+            if left <= range_len {
+                break;
+            }
+            left -= range_len + 1;
+            whole += 1;
+        }
+        let mut taken: Vec<(T, T)> = self.ranges.drain(..whole).collect();
+        if left > 0 {
+            if let Some(first) = self.ranges.first_mut() {
+                // range is longer than left, split it
+                let split = offset(first.0, left);
+                taken.push((first.0, split - T::one()));
+                first.0 = split;
+            }
+        }
+        RBSet { ranges: taken }
+    }
+
+    /// Remove and return the n largest values, or all of them if there are fewer.
+    pub fn take_largest(&mut self, n: usize) -> RBSet<T> {
+        let mut left = n as u128;
+        let mut whole = 0;
+        for &(start, end) in self.ranges.iter().rev() {
+            let range_len = distance(start, end);
+            if left <= range_len {
+                break;
+            }
+            left -= range_len + 1;
+            whole += 1;
+        }
+        let mut taken: Vec<(T, T)> = self.ranges.drain(self.ranges.len() - whole..).collect();
+        if left > 0 {
+            if let Some(last) = self.ranges.last_mut() {
+                // last.1 - left, counted from the start so it stays within the range
+                let split = offset(last.0, distance(last.0, last.1) - left);
+                taken.insert(0, (split + T::one(), last.1));
+                last.1 = split;
+            }
+        }
+        RBSet { ranges: taken }
+    }
+}
+
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> TryFrom<Vec<(T, T)>>
+    for RBSet<T>
+{
+    type Error = Error;
+
+    /// Ranges can be in any order and can overlap, they are sorted and coalesced.
+    fn try_from(mut ranges: Vec<(T, T)>) -> Result<Self, Self::Error> {
+        if let Some(index) = ranges.iter().position(|(start, end)| start > end) {
+            return Err(Error::StartAfterEnd { index });
+        }
+        ranges.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        let mut set = RBSet { ranges };
+        set.coalesce();
+        Ok(set)
+    }
+}
+
+// checks that ranges are sorted and coalesced, for types that borrow ranges as they are
+pub(crate) fn check_ranges<T: Num + PartialOrd + Copy>(
+    ranges: impl IntoIterator<Item = (T, T)>,
+) -> Result<(), Error> {
+    let mut prev_end = None;
+    for (index, (start, end)) in ranges.into_iter().enumerate() {
+        if start > end {
+            return Err(Error::StartAfterEnd { index });
+        }
+        if let Some(prev_end) = prev_end {
+            // start - 1 is only evaluated when start > prev_end
+            if start <= prev_end || start - T::one() == prev_end {
+                return Err(Error::NotCoalesced { index });
+            }
+        }
+        prev_end = Some(end);
+    }
+    Ok(())
+}
+
+// end - start for end >= start, without overflowing T
+pub(crate) fn distance<T: Num + Copy + ToPrimitive>(start: T, end: T) -> u128 {
+    match (start.to_i128(), end.to_i128()) {
+        (Some(start), Some(end)) => end.wrapping_sub(start) as u128,
+        // only u128 values don't fit, and they can't overflow
+        _ => (end - start).to_u128().unwrap(),
+    }
+}
+
+// start + by, computed in i128 or u128 so that it doesn't overflow T on the way, panics if
+// the result doesn't fit into T
+pub(crate) fn offset<T: Copy + ToPrimitive + NumCast>(start: T, by: u128) -> T {
+    let value = match (start.to_i128(), start.to_u128()) {
+        (Some(start), _) => match i128::try_from(by).ok().and_then(|by| start.checked_add(by)) {
+            Some(value) => T::from(value),
+            // start < 0 and by > i128::MAX, the result is below i128::MAX
+            None if start < 0 => T::from((start as u128).wrapping_add(by) as i128),
+            None => (start as u128).checked_add(by).and_then(T::from),
+        },
+        (None, Some(start)) => start.checked_add(by).and_then(T::from),
+        (None, None) => None,
+    };
+    value.expect("offset value doesn't fit into T")
+}
+
+// number of values in all ranges, in u128 as it doesn't fit usize for a full 64 bit range,
+// saturates at u128::MAX when the ranges cover all of u128 or i128
+pub(crate) fn count_values<T: Num + Copy + ToPrimitive>(
+    ranges: impl IntoIterator<Item = (T, T)>,
+) -> u128 {
+    ranges.into_iter().fold(0, |count: u128, (start, end)| {
+        count.saturating_add(distance(start, end)).saturating_add(1)
+    })
+}
+
+// count_values converted once, panics instead of truncating on 32 bit targets
+pub(crate) fn len_of<T: Num + Copy + ToPrimitive>(
+    ranges: impl IntoIterator<Item = (T, T)>,
+) -> usize {
+    to_len(count_values(ranges))
+}
+
+pub(crate) fn to_len(count: u128) -> usize {
+    usize::try_from(count).expect("number of values doesn't fit into usize")
+}
+
+// [start, end] equivalent of bounds, None if bounds are empty
+pub(crate) fn inclusive_bounds<T: Num + PartialOrd + Copy + Bounded>(
+    bounds: impl RangeBounds<T>,
+) -> Option<(T, T)> {
+    let start = match bounds.start_bound() {
+        Bound::Included(start) => *start,
+        Bound::Excluded(start) if *start == T::max_value() => return None,
+        Bound::Excluded(start) => *start + T::one(),
+        Bound::Unbounded => T::min_value(),
+    };
+    let end = match bounds.end_bound() {
+        Bound::Included(end) => *end,
+        Bound::Excluded(end) if *end == T::min_value() => return None,
+        Bound::Excluded(end) => *end - T::one(),
+        Bound::Unbounded => T::max_value(),
+    };
+    (start <= end).then_some((start, end))
+}
+
+// same as slice::partition_point, but walks from hint if it's provided
+pub(crate) fn partition_point_near<T>(
+    ranges: &[(T, T)],
+    hint: Option<usize>,
+    mut pred: impl FnMut(&(T, T)) -> bool,
+) -> usize {
+    let Some(mut pos) = hint else {
+        return ranges.partition_point(pred);
+    };
+    pos = pos.min(ranges.len());
+    while pos < ranges.len() && pred(&ranges[pos]) {
+        pos += 1;
+    }
+    while pos > 0 && !pred(&ranges[pos - 1]) {
+        pos -= 1;
+    }
+    pos
+}
+
+// ranges[window] is replaced with replacement
+pub(crate) struct RangesEdit<T> {
+    pub(crate) window: core::ops::Range<usize>,
+    pub(crate) replacement: [Option<(T, T)>; 2],
+}
+
+// entry of the union_all heap, ordered so that the smallest start is popped first
+struct NextRange<T> {
+    start: T,
+    set: usize,
+    idx: usize,
+}
+
+impl<T: PartialOrd> Ord for NextRange<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .start
+            .partial_cmp(&self.start)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl<T: PartialOrd> PartialOrd for NextRange<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: PartialOrd> PartialEq for NextRange<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: PartialOrd> Eq for NextRange<T> {}
+
+/// Which value [RBSet::closest_by] returns when two are equally close.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tie {
+    Lower,
+    Higher,
+}
+
+/// Changes needed to turn one set into another, see [RBSet::diff].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RBSetDelta<T> {
+    // [start, end]
+    pub insert: Vec<(T, T)>,
+    pub remove: Vec<(T, T)>,
+}
+
+impl<T> RBSetDelta<T> {
+    pub fn is_empty(&self) -> bool {
+        self.insert.is_empty() && self.remove.is_empty()
+    }
+}
+
+/// See [RBSet::stats].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RBSetStats {
+    pub range_count: usize,
+    /// Saturates at u128::MAX for a range covering all of u128 or i128
+    pub largest_range_len: u128,
+    pub smallest_range_len: u128,
+    /// last - first
+    pub span: u128,
+    /// Fraction of values in [first, last] that are in the set, 1.0 when there are no holes
+    pub density: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Range at `index` has start greater than end
+    StartAfterEnd { index: usize },
+    /// Range at `index` overlaps, touches or comes before the previous one
+    NotCoalesced { index: usize },
+    /// Byte length is not a multiple of the encoded range size
+    InvalidByteLength { len: usize },
+    /// Value is not a multiple of the stride of a StridedRBSet
+    NotOnStride,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::StartAfterEnd { index } => write!(f, "range {index} has start > end"),
+            Error::NotCoalesced { index } => {
+                write!(f, "range {index} is not after the previous one")
+            }
+            Error::InvalidByteLength { len } => {
+                write!(f, "{len} bytes is not a whole number of ranges")
+            }
+            Error::NotOnStride => write!(f, "value is not a multiple of the stride"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 impl<T: PartialOrd> Default for RBSet<T> {
     fn default() -> Self {
         Self { ranges: Vec::new() }
@@ -148,24 +1189,53 @@ impl<T: Display> Display for RBSet<T> {
 }
 
 pub struct RBSetIter<'i, T> {
-    set: &'i RBSet<T>,
+    ranges: &'i [(T, T)],
     pos: usize,
     last_yielded: Option<T>,
 }
 
+impl<T: Num + AddAssign + PartialOrd + Copy> RBSetIter<'_, T> {
+    /// Token to continue from the next value with [RBSet::iter_resume], None if exhausted.
+    ///
+    /// The token only stores a value, so it stays usable if the set is changed in between.
+    pub fn resume_token(&self) -> Option<Resume<T>> {
+        let range = self.ranges.get(self.pos)?;
+        let next = match self.last_yielded {
+            Some(x) if x == range.1 => self.ranges.get(self.pos + 1)?.0,
+            Some(x) => x + T::one(),
+            None => range.0,
+        };
+        Some(Resume { next })
+    }
+}
+
+/// Position to continue iterating at, see [RBSetIter::resume_token].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Resume<T> {
+    next: T,
+}
+
+impl<T: Copy> Resume<T> {
+    /// First value the resumed iterator will consider.
+    pub fn value(&self) -> T {
+        self.next
+    }
+}
+
 impl<'i, T: Num + AddAssign + PartialOrd + Copy> Iterator for RBSetIter<'i, T> {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.pos >= self.set.ranges.len() {
+        if self.pos >= self.ranges.len() {
             None
         } else {
             match &mut self.last_yielded {
                 Some(x) => {
-                    if *x == self.set.ranges[self.pos].1 {
+                    if *x == self.ranges[self.pos].1 {
                         self.pos += 1;
-                        if self.pos < self.set.ranges.len() {
-                            *x = self.set.ranges[self.pos].0;
+                        if self.pos < self.ranges.len() {
+                            *x = self.ranges[self.pos].0;
                             Some(*x)
                         } else {
                             None
@@ -176,7 +1246,7 @@ impl<'i, T: Num + AddAssign + PartialOrd + Copy> Iterator for RBSetIter<'i, T> {
                     }
                 }
                 None => {
-                    self.last_yielded = Some(self.set.ranges[0].0);
+                    self.last_yielded = Some(self.ranges[self.pos].0);
                     self.last_yielded
                 }
             }
@@ -242,4 +1312,554 @@ mod tests {
         assert_eq!(iter.next(), Some(10));
         assert!(iter.next().is_none());
     }
+
+    #[test]
+    fn try_from_ranges() {
+        let set = RBSet::try_from(vec![(7, 8), (0, 2), (3, 3), (10, 12), (11, 15)]).unwrap();
+        assert_eq!(set.ranges(), &[(0, 3), (7, 8), (10, 15)]);
+        assert_eq!(set.len(), 12);
+
+        let set = RBSet::<u8>::try_from(vec![]).unwrap();
+        assert!(set.is_empty());
+
+        let set = RBSet::<u8>::try_from(vec![(250, 255), (0, 255)]).unwrap();
+        assert_eq!(set.ranges(), &[(0, 255)]);
+
+        assert_eq!(
+            RBSet::try_from(vec![(0, 2), (5, 4)]).unwrap_err(),
+            Error::StartAfterEnd { index: 1 }
+        );
+    }
+
+    #[test]
+    fn from_sorted_ranges_unchecked() {
+        let set = RBSet::from_sorted_ranges_unchecked(vec![(0u32, 3), (7, 8)]);
+        assert!(set.contains(&8));
+        assert!(!set.contains(&5));
+        assert_eq!(set.len(), 6);
+    }
+
+    #[test]
+    fn from_sorted_iter() {
+        let set = RBSet::from_sorted_iter([0u8, 1, 1, 2, 3, 7, 8, 10, 255].into_iter());
+        assert_eq!(set.ranges(), &[(0, 3), (7, 8), (10, 10), (255, 255)]);
+
+        let set = RBSet::<i32>::from_sorted_iter(core::iter::empty());
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "input is not sorted")]
+    fn from_sorted_iter_unsorted() {
+        RBSet::from_sorted_iter([0, 1, 2, 5, 1].into_iter());
+    }
+
+    #[test]
+    fn from_unsorted() {
+        let mut values = [10, 3, 8, 0, 2, 1, 7, 3, 0, -5, -4];
+        let set = RBSet::from_unsorted(&mut values);
+        assert_eq!(set.ranges(), &[(-5, -4), (0, 3), (7, 8), (10, 10)]);
+        assert!(RBSet::<u16>::from_unsorted(&mut []).is_empty());
+    }
+
+    #[test]
+    fn insert_range() {
+        let mut set = RBSet::<u8>::new();
+        set.insert_range(5, 7);
+        set.insert_range(10, 12);
+        set.insert_range(20, 20);
+        assert_eq!(set.ranges(), &[(5, 7), (10, 12), (20, 20)]);
+        set.insert_range(8, 9);
+        assert_eq!(set.ranges(), &[(5, 12), (20, 20)]);
+        set.insert_range(0, 3);
+        set.insert_range(15, 18);
+        assert_eq!(set.ranges(), &[(0, 3), (5, 12), (15, 18), (20, 20)]);
+        set.insert_range(2, 16);
+        assert_eq!(set.ranges(), &[(0, 18), (20, 20)]);
+        set.insert_range(250, 255);
+        set.insert_range(21, 249);
+        assert_eq!(set.ranges(), &[(0, 18), (20, 255)]);
+        set.insert_range(3, 1);
+        assert_eq!(set.ranges(), &[(0, 18), (20, 255)]);
+    }
+
+    #[test]
+    fn insert_range_counted() {
+        let mut set = RBSet::<u8>::try_from(vec![(0, 10), (20, 30)]).unwrap();
+        assert_eq!(set.insert_range_counted(5, 25), 9);
+        assert_eq!(set.insert_range_counted(0, 30), 0);
+        assert_eq!(set.insert_range_counted(31, 31), 1);
+        assert_eq!(set.insert_range_counted(40, 255), 216);
+        assert_eq!(set.insert_range_counted(3, 1), 0);
+        assert_eq!(set.ranges(), &[(0, 31), (40, 255)]);
+
+        let mut set = RBSet::<u128>::new();
+        assert_eq!(set.insert_range_counted(5, u128::MAX), u128::MAX - 4);
+        assert_eq!(set.insert_range_counted(0, u128::MAX), 5);
+        let mut set = RBSet::<i128>::new();
+        assert_eq!(set.insert_range_counted(i128::MIN, i128::MAX), u128::MAX);
+    }
+
+    #[test]
+    fn insert_full_u64_range() {
+        let mut set = RBSet::<u64>::new();
+        set.insert_range(0, u64::MAX);
+        assert_eq!(set.ranges(), &[(0, u64::MAX)]);
+        let mut set = RBSet::<u64>::try_from(vec![(10, 20)]).unwrap();
+        assert_eq!(set.insert_range_counted(0, u64::MAX), u64::MAX as u128 - 10);
+        let mut delta_target = RBSet::<u64>::new();
+        delta_target.apply(&RBSet::new().diff(&set));
+        assert_eq!(delta_target, set);
+    }
+
+    #[test]
+    fn remove_range() {
+        let mut set = RBSet::<u8>::try_from(vec![(0, 10), (20, 30), (40, 50)]).unwrap();
+        set.remove_range(12, 18);
+        assert_eq!(set.ranges(), &[(0, 10), (20, 30), (40, 50)]);
+        set.remove_range(3, 5);
+        assert_eq!(set.ranges(), &[(0, 2), (6, 10), (20, 30), (40, 50)]);
+        set.remove_range(8, 45);
+        assert_eq!(set.ranges(), &[(0, 2), (6, 7), (46, 50)]);
+        set.remove_range(0, 2);
+        set.remove_range(50, 255);
+        assert_eq!(set.ranges(), &[(6, 7), (46, 49)]);
+        set.remove_range(0, 255);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn remove_up_to() {
+        let mut set = RBSet::<u8>::try_from(vec![(0, 10), (20, 30), (40, 255)]).unwrap();
+        set.remove_up_to(15);
+        assert_eq!(set.ranges(), &[(20, 30), (40, 255)]);
+        set.remove_up_to(20);
+        assert_eq!(set.ranges(), &[(21, 30), (40, 255)]);
+        set.remove_up_to(254);
+        assert_eq!(set.ranges(), &[(255, 255)]);
+        set.remove_up_to(255);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn algebra_len() {
+        let a = RBSet::<u8>::try_from(vec![(0, 10), (20, 30), (250, 255)]).unwrap();
+        let b = RBSet::<u8>::try_from(vec![(5, 25), (240, 255)]).unwrap();
+        assert_eq!(a.intersection_len(&b), a.intersection(&b).len());
+        assert_eq!(a.union_len(&b), a.union(&b).len());
+        assert_eq!(a.difference_len(&b), a.difference(&b).len());
+        assert_eq!(b.difference_len(&a), b.difference(&a).len());
+        assert_eq!(a.union_len(&RBSet::new()), a.len());
+        let half = RBSet::<u64>::try_from(vec![(0, 1 << 63)]).unwrap();
+        assert_eq!(half.union_len(&half), (1 << 63) + 1);
+        assert_eq!(half.difference_len(&half), 0);
+        let upper = RBSet::<i128>::try_from(vec![(0, i128::MAX)]).unwrap();
+        let small = RBSet::<i128>::try_from(vec![(-10, 9)]).unwrap();
+        assert_eq!(small.difference_len(&upper), 10);
+        assert_eq!(small.intersection_len(&upper), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "number of values doesn't fit into usize")]
+    fn union_len_past_u128() {
+        let full = RBSet::<i128>::try_from(vec![(i128::MIN, i128::MAX)]).unwrap();
+        full.union_len(&full);
+    }
+
+    #[test]
+    fn similarity() {
+        let a = RBSet::<u64>::try_from(vec![(0, 99)]).unwrap();
+        let b = RBSet::<u64>::try_from(vec![(50, 149)]).unwrap();
+        assert_eq!(a.jaccard(&b), 50.0 / 150.0);
+        assert_eq!(a.overlap_coefficient(&b), 0.5);
+        let full = RBSet::<u64>::try_from(vec![(0, u64::MAX)]).unwrap();
+        let half = RBSet::<u64>::try_from(vec![(0, u64::MAX / 2)]).unwrap();
+        assert_eq!(full.jaccard(&half), 0.5);
+        assert_eq!(full.overlap_coefficient(&half), 1.0);
+        assert_eq!(RBSet::<u64>::new().jaccard(&RBSet::new()), 1.0);
+        assert_eq!(a.jaccard(&RBSet::new()), 0.0);
+        let full = RBSet::<u128>::try_from(vec![(0, u128::MAX)]).unwrap();
+        assert_eq!(full.jaccard(&full), 1.0);
+        assert_eq!(full.overlap_coefficient(&full), 1.0);
+        let full = RBSet::<i128>::try_from(vec![(i128::MIN, i128::MAX)]).unwrap();
+        let upper = RBSet::<i128>::try_from(vec![(0, i128::MAX)]).unwrap();
+        assert_eq!(full.jaccard(&upper), 0.5);
+        assert_eq!(upper.jaccard(&full), 0.5);
+        assert_eq!(full.overlap_coefficient(&upper), 1.0);
+    }
+
+    #[test]
+    fn resume() {
+        let mut set = RBSet::<u8>::try_from(vec![(0, 2), (5, 6), (255, 255)]).unwrap();
+        assert_eq!(set.iter_from(&1).collect::<Vec<_>>(), vec![1, 2, 5, 6, 255]);
+        assert_eq!(set.iter_from(&3).collect::<Vec<_>>(), vec![5, 6, 255]);
+        assert_eq!(set.iter_from(&255).collect::<Vec<_>>(), vec![255]);
+
+        let mut iter = set.iter();
+        assert_eq!(iter.resume_token().map(|t| t.value()), Some(0));
+        assert_eq!(iter.by_ref().take(3).collect::<Vec<_>>(), vec![0, 1, 2]);
+        let token = iter.resume_token().unwrap();
+        assert_eq!(token.value(), 5);
+        set.insert(100);
+        assert_eq!(
+            set.iter_resume(token).collect::<Vec<_>>(),
+            vec![5, 6, 100, 255]
+        );
+        let mut iter = set.iter_from(&255);
+        iter.next();
+        assert_eq!(iter.resume_token(), None);
+    }
+
+    #[test]
+    fn ranges_exclusive() {
+        let mut set = RBSet::<u8>::try_from(vec![(0, 2), (5, 5), (250, 254)]).unwrap();
+        let (ranges, tail) = set.ranges_exclusive();
+        assert_eq!(ranges.collect::<Vec<_>>(), vec![0..3, 5..6, 250..255]);
+        assert_eq!(tail, None);
+        set.insert(255);
+        let (ranges, tail) = set.ranges_exclusive();
+        assert_eq!(ranges.rev().collect::<Vec<_>>(), vec![5..6, 0..3]);
+        assert_eq!(tail, Some(250..));
+    }
+
+    #[test]
+    fn aggregates() {
+        let set = RBSet::<i16>::try_from(vec![(-10, -5), (0, 3), (100, 100)]).unwrap();
+        let values: Vec<i16> = set.iter().collect();
+        assert_eq!(set.sum(), values.iter().map(|&x| x as i128).sum::<i128>());
+        assert_eq!(set.mean(), Some(set.sum() as f64 / values.len() as f64));
+        assert_eq!((set.first(), set.last()), (Some(-10), Some(100)));
+
+        let full = RBSet::<u64>::try_from(vec![(0, u64::MAX)]).unwrap();
+        assert_eq!(full.sum(), u64::MAX as i128 * (1 << 63));
+        let huge = RBSet::<u128>::try_from(vec![(0, u128::MAX)]).unwrap();
+        assert_eq!(huge.checked_sum(), None);
+        assert_eq!(huge.mean(), Some(u128::MAX as f64 / 2.0));
+        let full = RBSet::<i128>::try_from(vec![(i128::MIN, i128::MAX)]).unwrap();
+        assert_eq!(full.checked_sum(), None);
+        assert_eq!(full.mean(), Some(0.0));
+        assert_eq!(RBSet::<u8>::new().sum(), 0);
+        assert_eq!(RBSet::<u8>::new().mean(), None);
+    }
+
+    #[test]
+    fn ordering() {
+        use std::collections::BTreeMap;
+
+        let a = RBSet::<u8>::try_from(vec![(0, 3), (10, 10)]).unwrap();
+        let b = RBSet::<u8>::try_from(vec![(0, 5)]).unwrap();
+        let c = RBSet::<u8>::try_from(vec![(0, 3)]).unwrap();
+        assert!(c < a && a < b);
+        let mut built = RBSet::new();
+        built.insert_range(0, 2);
+        built.insert(3);
+        assert_eq!(built, c);
+        let mut cache = BTreeMap::new();
+        cache.insert(a.clone(), 1);
+        cache.insert(c, 2);
+        assert_eq!(cache.get(&built), Some(&2));
+        assert!(RBSet::new() < a);
+    }
+
+    #[test]
+    fn coalesce_with_tolerance() {
+        let mut set =
+            RBSet::<i8>::try_from(vec![(-128, -120), (-118, 0), (3, 5), (9, 127)]).unwrap();
+        set.coalesce_with_tolerance(0);
+        assert_eq!(set.ranges().len(), 4);
+        set.coalesce_with_tolerance(2);
+        assert_eq!(set.ranges(), &[(-128, 5), (9, 127)]);
+        set.coalesce_with_tolerance(3);
+        assert_eq!(set.ranges(), &[(-128, 127)]);
+        let mut empty = RBSet::<u8>::new();
+        empty.coalesce_with_tolerance(10);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn diff_apply() {
+        let a = RBSet::<i32>::try_from(vec![(0, 10), (20, 30), (40, 50)]).unwrap();
+        let b = RBSet::<i32>::try_from(vec![(-5, 2), (5, 25), (45, 60)]).unwrap();
+        let delta = a.diff(&b);
+        assert_eq!(delta.insert, vec![(-5, -1), (11, 19), (51, 60)]);
+        assert_eq!(delta.remove, vec![(3, 4), (26, 30), (40, 44)]);
+        let mut patched = a.clone();
+        patched.apply(&delta);
+        assert_eq!(patched.ranges(), b.ranges());
+
+        let mut patched = b.clone();
+        patched.apply(&b.diff(&a));
+        assert_eq!(patched.ranges(), a.ranges());
+
+        assert!(a.diff(&a).is_empty());
+    }
+
+    #[test]
+    fn contiguous() {
+        let mut set = RBSet::<u8>::new();
+        assert!(!set.is_contiguous());
+        assert_eq!(set.as_single_range(), None);
+        set.insert_range(0, 3);
+        set.insert_range(5, 7);
+        assert!(!set.is_contiguous());
+        set.insert(4);
+        assert!(set.is_contiguous());
+        assert_eq!(set.as_single_range(), Some(0..=7));
+    }
+
+    #[test]
+    fn closest() {
+        let set = RBSet::<u8>::try_from(vec![(0, 3), (7, 8), (255, 255)]).unwrap();
+        assert_eq!(set.closest(&2), Some(2));
+        assert_eq!(set.closest(&4), Some(3));
+        assert_eq!(set.closest(&5), Some(3));
+        assert_eq!(set.closest_by(&5, Tie::Higher), Some(7));
+        assert_eq!(set.closest(&6), Some(7));
+        assert_eq!(set.closest(&200), Some(255));
+        assert_eq!(RBSet::<u8>::new().closest(&0), None);
+    }
+
+    #[test]
+    fn get_range() {
+        let set = RBSet::<u8>::try_from(vec![(0, 3), (7, 8), (255, 255)]).unwrap();
+        assert_eq!(set.get_range(&2), Some(0..=3));
+        assert_eq!(set.get_range(&7), Some(7..=8));
+        assert_eq!(set.get_range(&255), Some(255..=255));
+        assert_eq!(set.get_range(&5), None);
+        assert_eq!(set.get_range(&100), None);
+        assert_eq!(RBSet::<u8>::new().get_range(&0), None);
+    }
+
+    #[test]
+    fn overlaps() {
+        let set = RBSet::<i32>::try_from(vec![(0, 3), (7, 8), (10, 20)]).unwrap();
+        assert!(set.overlaps(3..=5));
+        assert!(set.overlaps(-10..=100));
+        assert!(!set.overlaps(4..=6));
+        assert!(!set.overlaps(21..=30));
+        assert!(!set.overlaps(RangeInclusive::new(8, 7)));
+        assert_eq!(
+            set.overlapping_ranges(2..=10).collect::<Vec<_>>(),
+            vec![0..=3, 7..=8, 10..=20]
+        );
+        assert_eq!(set.overlapping_ranges(9..=9).count(), 0);
+    }
+
+    #[test]
+    fn select() {
+        let set = RBSet::<i8>::try_from(vec![(-128, -126), (7, 8), (120, 127)]).unwrap();
+        let all: Vec<i8> = set.iter().collect();
+        for (idx, value) in all.iter().enumerate() {
+            assert_eq!(set.select(idx), Some(*value));
+        }
+        assert_eq!(set.select(all.len()), None);
+        assert_eq!(RBSet::<u8>::new().select(0), None);
+        let full = RBSet::<i8>::try_from(vec![(-128, 127)]).unwrap();
+        assert_eq!(full.select(200), Some(72));
+        let full = RBSet::<i128>::try_from(vec![(i128::MIN, i128::MAX)]).unwrap();
+        assert_eq!(
+            full.select(usize::MAX),
+            Some(i128::MIN + usize::MAX as i128)
+        );
+    }
+
+    #[test]
+    fn sample_with() {
+        let set = RBSet::<u8>::try_from(vec![(0, 3), (200, 201)]).unwrap();
+        assert_eq!(set.sample_with(|max| max), Some(201));
+        assert_eq!(set.sample_with(|_| 4), Some(200));
+        assert_eq!(RBSet::<u8>::new().sample_with(|_| unreachable!()), None);
+        let full = RBSet::<u64>::try_from(vec![(0, u64::MAX)]).unwrap();
+        assert_eq!(full.sample_with(|max| max), Some(u64::MAX));
+        let full = RBSet::<i128>::try_from(vec![(i128::MIN, -1), (0, i128::MAX)]).unwrap();
+        assert_eq!(full.sample_with(|max| max), Some(i128::MAX));
+        assert_eq!(full.sample_with(|max| max / 2 + 1), Some(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "pick returned an index past max")]
+    fn sample_with_past_max() {
+        let set = RBSet::<u8>::try_from(vec![(0, 3)]).unwrap();
+        set.sample_with(|max| max + 1);
+    }
+
+    #[test]
+    fn stats() {
+        assert_eq!(RBSet::<u32>::new().stats(), None);
+        let set = RBSet::<u32>::try_from(vec![(0, 3), (7, 8), (10, 10), (15, 19)]).unwrap();
+        let stats = set.stats().unwrap();
+        assert_eq!(stats.range_count, 4);
+        assert_eq!(stats.largest_range_len, 5);
+        assert_eq!(stats.smallest_range_len, 1);
+        assert_eq!(stats.span, 19);
+        assert_eq!(stats.density, 0.6);
+        let set = RBSet::<i8>::try_from(vec![(-128, 127)]).unwrap();
+        assert_eq!(set.stats().unwrap().density, 1.0);
+        let set = RBSet::<u64>::try_from(vec![(0, u64::MAX)]).unwrap();
+        let stats = set.stats().unwrap();
+        assert_eq!(stats.largest_range_len, 1 << 64);
+        assert_eq!(stats.smallest_range_len, 1 << 64);
+        assert_eq!(stats.span, u64::MAX as u128);
+        assert_eq!(stats.density, 1.0);
+        let set = RBSet::<i64>::try_from(vec![(i64::MIN, -1), (1, i64::MAX)]).unwrap();
+        let stats = set.stats().unwrap();
+        assert_eq!(stats.largest_range_len, 1 << 63);
+        assert_eq!(stats.smallest_range_len, (1 << 63) - 1);
+    }
+
+    #[test]
+    fn iter_chunks() {
+        let set = RBSet::<u32>::try_from(vec![(0, 3), (7, 8), (10, 10)]).unwrap();
+        let chunks: Vec<Vec<u32>> = set.iter_chunks(3).collect();
+        assert_eq!(chunks, vec![vec![0, 1, 2], vec![3, 7, 8], vec![10]]);
+        assert_eq!(set.iter_chunks(7).count(), 1);
+        assert_eq!(RBSet::<u32>::new().iter_chunks(2).count(), 0);
+    }
+
+    #[test]
+    fn push_max() {
+        let mut set = RBSet::<u8>::new();
+        set.push_max(0);
+        set.push_max(1);
+        set.push_max(1);
+        set.push_max(5);
+        set.push_max(255);
+        assert_eq!(set.ranges(), &[(0, 1), (5, 5), (255, 255)]);
+        set.insert(254);
+        assert_eq!(set.ranges(), &[(0, 1), (5, 5), (254, 255)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn push_max_below_max() {
+        let mut set = RBSet::<u8>::new();
+        set.push_max(5);
+        set.push_max(4);
+    }
+
+    #[test]
+    fn rbset_macro() {
+        let set: RBSet<u8> = rbset![0..=5, 9, 20..=30, 6, 25..=40];
+        assert_eq!(set.ranges(), &[(0, 6), (9, 9), (20, 40)]);
+        let set = rbset![-3, -5..=-4];
+        assert_eq!(set.ranges(), &[(-5, -3)]);
+        let set: RBSet<u64> = rbset![];
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn union_all() {
+        let a = RBSet::<i32>::try_from(vec![(0, 10), (40, 50)]).unwrap();
+        let b = RBSet::<i32>::try_from(vec![(-5, -1), (12, 20)]).unwrap();
+        let c = RBSet::<i32>::try_from(vec![(11, 11), (45, 60), (100, 100)]).unwrap();
+        let empty = RBSet::new();
+        let union = RBSet::union_all(&[&a, &b, &empty, &c]);
+        assert_eq!(union.ranges(), &[(-5, 20), (40, 60), (100, 100)]);
+        assert_eq!(union.ranges(), a.union(&b).union(&c).ranges());
+        assert!(RBSet::<i32>::union_all(&[]).is_empty());
+    }
+
+    #[test]
+    fn retain_ranges() {
+        let mut set = RBSet::<u8>::try_from(vec![(0, 3), (7, 8), (10, 10), (20, 255)]).unwrap();
+        set.retain_ranges(|r| r.end() - r.start() >= 2);
+        assert_eq!(set.ranges(), &[(0, 3), (20, 255)]);
+        set.retain_ranges(|_| false);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn take_smallest() {
+        let mut set = RBSet::<u8>::try_from(vec![(0, 3), (7, 8), (10, 10), (250, 255)]).unwrap();
+        assert_eq!(set.take_smallest(0).ranges(), &[]);
+        assert_eq!(set.take_smallest(2).ranges(), &[(0, 1)]);
+        assert_eq!(set.take_smallest(4).ranges(), &[(2, 3), (7, 8)]);
+        assert_eq!(set.take_smallest(3).ranges(), &[(10, 10), (250, 251)]);
+        assert_eq!(set.ranges(), &[(252, 255)]);
+        assert_eq!(set.take_smallest(100).ranges(), &[(252, 255)]);
+        assert!(set.is_empty());
+        let mut full = RBSet::<i8>::try_from(vec![(-128, 127)]).unwrap();
+        assert_eq!(full.take_smallest(200).ranges(), &[(-128, 71)]);
+        assert_eq!(full.ranges(), &[(72, 127)]);
+    }
+
+    #[test]
+    fn take_largest() {
+        let mut set =
+            RBSet::<i8>::try_from(vec![(-128, -126), (7, 8), (10, 10), (120, 127)]).unwrap();
+        assert_eq!(set.take_largest(3).ranges(), &[(125, 127)]);
+        assert_eq!(set.take_largest(6).ranges(), &[(10, 10), (120, 124)]);
+        assert_eq!(set.take_largest(3).ranges(), &[(-126, -126), (7, 8)]);
+        assert_eq!(set.ranges(), &[(-128, -127)]);
+        assert_eq!(set.take_largest(5).ranges(), &[(-128, -127)]);
+        assert!(set.take_largest(1).is_empty());
+        let mut full = RBSet::<i8>::try_from(vec![(-128, 127)]).unwrap();
+        assert_eq!(full.take_largest(200).ranges(), &[(-72, 127)]);
+        assert_eq!(full.ranges(), &[(-128, -73)]);
+    }
+
+    #[test]
+    fn next_free_slot() {
+        let set = RBSet::<u32>::try_from(vec![(0, 4095), (4200, 8191), (12288, 12300)]).unwrap();
+        assert_eq!(set.next_free_slot(0, 4096, 4096), Some(8192));
+        assert_eq!(set.next_free_slot(0, 4097, 4096), Some(16384));
+        assert_eq!(set.next_free_slot(0, 100, 1), Some(4096));
+        assert_eq!(set.next_free_slot(0, 100, 64), Some(4096));
+        assert_eq!(set.next_free_slot(0, 105, 64), Some(8192));
+        assert_eq!(set.next_free_slot(4097, 4, 4), Some(4100));
+        assert_eq!(set.next_free_slot(12290, 1, 1), Some(12301));
+        assert_eq!(RBSet::<u32>::new().next_free_slot(5, 10, 8), Some(8));
+
+        let set = RBSet::<u8>::try_from(vec![(0, 200), (250, 255)]).unwrap();
+        assert_eq!(set.next_free_slot(0, 40, 8), Some(208));
+        assert_eq!(set.next_free_slot(0, 50, 1), None);
+        assert_eq!(set.next_free_slot(0, 1, 128), None);
+
+        let set = RBSet::<i8>::try_from(vec![(-100, -50)]).unwrap();
+        assert_eq!(set.next_free_slot(-128, 16, 16), Some(-128));
+        assert_eq!(set.next_free_slot(-110, 16, 16), Some(-48));
+        assert_eq!(set.next_free_slot(-110, 16, 10), Some(-40));
+        assert_eq!(set.next_free_slot(120, 8, 8), Some(120));
+        assert_eq!(set.next_free_slot(121, 1, 8), None);
+    }
+
+    #[test]
+    fn contains_all_any() {
+        let set = RBSet::<u32>::try_from(vec![(0, 3), (7, 8), (10, 10)]).unwrap();
+        assert!(set.contains_all([10, 0, 8, 2, 2]));
+        assert!(!set.contains_all([10, 0, 9]));
+        assert!(set.contains_all([]));
+        assert!(set.contains_any([100, 5, 7]));
+        assert!(!set.contains_any([100, 5, 11, 4]));
+        assert!(!set.contains_any([]));
+        assert!(!RBSet::<u32>::new().contains_all([1]));
+    }
+
+    #[test]
+    fn boundaries() {
+        let mut set = RBSet::<i8>::new();
+        set.insert_range(-128, 127);
+        assert_eq!(set.len(), 256);
+        set.remove(&-128);
+        set.remove(&127);
+        assert_eq!(set.ranges(), &[(-127, 126)]);
+        set.insert(127);
+        set.insert(-128);
+        assert_eq!(set.ranges(), &[(-128, 127)]);
+        assert_eq!(set.iter().last(), Some(127));
+        set.remove_range(-128, -1);
+        assert_eq!(set.ranges(), &[(0, 127)]);
+        assert_eq!(RBSet::<u8>::try_from(vec![(0, 255)]).unwrap().len(), 256);
+    }
+
+    #[test]
+    fn iter_ranges_rev() {
+        let set = RBSet::<u32>::try_from(vec![(0, 3), (7, 8), (10, 10)]).unwrap();
+        let rev: Vec<_> = set.iter_ranges().rev().collect();
+        assert_eq!(rev, vec![(10, 10), (7, 8), (0, 3)]);
+        assert_eq!(set.iter_ranges().len(), 3);
+        assert_eq!(set.last_ranges(2), &[(7, 8), (10, 10)]);
+        assert_eq!(set.last_ranges(5), set.ranges());
+        assert!(set.last_ranges(0).is_empty());
+    }
 }
@@ -0,0 +1,47 @@
+use std::ops::RangeInclusive;
+
+/// Build an RBSet from single values and inclusive ranges.
+///
+/// Items can be in any order and overlap, they are sorted and coalesced.
+/// Panics if a range has start greater than end.
+///
+/// ```
+/// let set: rbset::RBSet<u32> = rbset::rbset![20..=30, 0..=5, 9];
+/// assert_eq!(set.ranges(), &[(0, 5), (9, 9), (20, 30)]);
+/// ```
+#[macro_export]
+macro_rules! rbset {
+    () => {
+        $crate::RBSet::new()
+    };
+    ($($item:expr),+ $(,)?) => {
+        $crate::RBSet::try_from(vec![$($crate::RangeItem::bounds($item)),+])
+            .expect("rbset!: range start is greater than end")
+    };
+}
+
+/// Value or range accepted by [rbset!].
+pub trait RangeItem<T> {
+    /// [start, end]
+    fn bounds(self) -> (T, T);
+}
+
+impl<T> RangeItem<T> for RangeInclusive<T> {
+    fn bounds(self) -> (T, T) {
+        self.into_inner()
+    }
+}
+
+macro_rules! impl_range_item {
+    ($($t:ty),*) => {
+        $(
+            impl RangeItem<$t> for $t {
+                fn bounds(self) -> ($t, $t) {
+                    (self, self)
+                }
+            }
+        )*
+    };
+}
+
+impl_range_item!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
@@ -0,0 +1,197 @@
+use std::{
+    iter::{Copied, FusedIterator, Peekable},
+    slice,
+};
+
+use num_traits::Num;
+
+/// Ranges of a set as an iterator, what the merge iterators below are built from.
+pub type SliceRanges<'a, T> = Copied<slice::Iter<'a, (T, T)>>;
+
+/// Ranges of the union of two sets, computed lazily, see [crate::RBSet::union_iter].
+///
+/// Inputs must yield sorted and coalesced ranges, as [crate::RBSet::ranges] does.
+pub struct UnionRanges<A: Iterator, B: Iterator> {
+    a: Peekable<A>,
+    b: Peekable<B>,
+    pending: Option<A::Item>,
+}
+
+/// Ranges of the intersection of two sets, see [crate::RBSet::intersection_iter].
+pub struct IntersectionRanges<A: Iterator, B: Iterator> {
+    a: Peekable<A>,
+    b: Peekable<B>,
+}
+
+/// Ranges in the first set but not in the second, see [crate::RBSet::difference_iter].
+pub struct DifferenceRanges<A: Iterator, B: Iterator> {
+    a: A,
+    b: Peekable<B>,
+    // what is left of the last range taken from a
+    current: Option<A::Item>,
+}
+
+impl<T, A: Iterator<Item = (T, T)>, B: Iterator<Item = (T, T)>> UnionRanges<A, B> {
+    pub fn new(a: impl IntoIterator<IntoIter = A>, b: impl IntoIterator<IntoIter = B>) -> Self {
+        UnionRanges {
+            a: a.into_iter().peekable(),
+            b: b.into_iter().peekable(),
+            pending: None,
+        }
+    }
+}
+
+impl<T, A: Iterator<Item = (T, T)>, B: Iterator<Item = (T, T)>> IntersectionRanges<A, B> {
+    pub fn new(a: impl IntoIterator<IntoIter = A>, b: impl IntoIterator<IntoIter = B>) -> Self {
+        IntersectionRanges {
+            a: a.into_iter().peekable(),
+            b: b.into_iter().peekable(),
+        }
+    }
+}
+
+impl<T, A: Iterator<Item = (T, T)>, B: Iterator<Item = (T, T)>> DifferenceRanges<A, B> {
+    pub fn new(a: impl IntoIterator<IntoIter = A>, b: impl IntoIterator<IntoIter = B>) -> Self {
+        DifferenceRanges {
+            a: a.into_iter(),
+            b: b.into_iter().peekable(),
+            current: None,
+        }
+    }
+}
+
+impl<T, A, B> Iterator for UnionRanges<A, B>
+where
+    T: Num + PartialOrd + Copy,
+    A: Iterator<Item = (T, T)>,
+    B: Iterator<Item = (T, T)>,
+{
+    type Item = (T, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let next = match (self.a.peek(), self.b.peek()) {
+                (Some(a), Some(b)) if a.0 <= b.0 => self.a.next(),
+                (Some(_), None) => self.a.next(),
+                (_, Some(_)) => self.b.next(),
+                (None, None) => return self.pending.take(),
+            };
+            let next = next.unwrap();
+            match &mut self.pending {
+                // next.0 - 1 is only evaluated when next.0 > pending.1
+                Some(pending) if next.0 <= pending.1 || next.0 - T::one() == pending.1 => {
+                    if next.1 > pending.1 {
+                        pending.1 = next.1;
+                    }
+                }
+                pending => {
+                    if let Some(done) = pending.replace(next) {
+                        return Some(done);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T, A, B> Iterator for IntersectionRanges<A, B>
+where
+    T: PartialOrd + Copy,
+    A: Iterator<Item = (T, T)>,
+    B: Iterator<Item = (T, T)>,
+{
+    type Item = (T, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let (Some(&a), Some(&b)) = (self.a.peek(), self.b.peek()) {
+            let start = if a.0 > b.0 { a.0 } else { b.0 };
+            let end = if a.1 < b.1 { a.1 } else { b.1 };
+            if a.1 < b.1 {
+                self.a.next();
+            } else {
+                self.b.next();
+            }
+            if start <= end {
+                return Some((start, end));
+            }
+        }
+        None
+    }
+}
+
+impl<T, A, B> Iterator for DifferenceRanges<A, B>
+where
+    T: Num + PartialOrd + Copy,
+    A: Iterator<Item = (T, T)>,
+    B: Iterator<Item = (T, T)>,
+{
+    type Item = (T, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (start, end) = match self.current.take() {
+                Some(current) => current,
+                None => self.a.next()?,
+            };
+            while self.b.peek().is_some_and(|b| b.1 < start) {
+                self.b.next();
+            }
+            let Some(&b) = self.b.peek().filter(|b| b.0 <= end) else {
+                return Some((start, end));
+            };
+            // b overlaps [start, end], b may overlap the next range as well if it goes past end
+            if b.1 < end {
+                self.current = Some((b.1 + T::one(), end));
+                self.b.next();
+            }
+            if b.0 > start {
+                return Some((start, b.0 - T::one()));
+            }
+        }
+    }
+}
+
+impl<T, A, B> FusedIterator for UnionRanges<A, B>
+where
+    T: Num + PartialOrd + Copy,
+    A: FusedIterator<Item = (T, T)>,
+    B: FusedIterator<Item = (T, T)>,
+{
+}
+
+impl<T, A, B> FusedIterator for IntersectionRanges<A, B>
+where
+    T: PartialOrd + Copy,
+    A: FusedIterator<Item = (T, T)>,
+    B: FusedIterator<Item = (T, T)>,
+{
+}
+
+impl<T, A, B> FusedIterator for DifferenceRanges<A, B>
+where
+    T: Num + PartialOrd + Copy,
+    A: FusedIterator<Item = (T, T)>,
+    B: FusedIterator<Item = (T, T)>,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RBSet;
+
+    #[test]
+    fn lazy() {
+        let a = RBSet::<u8>::try_from(vec![(0, 10), (20, 30), (40, 50), (255, 255)]).unwrap();
+        let b = RBSet::<u8>::try_from(vec![(5, 25), (31, 32), (45, 60)]).unwrap();
+        let union: Vec<_> = a.union_iter(&b).collect();
+        assert_eq!(union, vec![(0, 32), (40, 60), (255, 255)]);
+        let intersection: Vec<_> = a.intersection_iter(&b).collect();
+        assert_eq!(intersection, vec![(5, 10), (20, 25), (45, 50)]);
+        let difference: Vec<_> = a.difference_iter(&b).collect();
+        assert_eq!(difference, vec![(0, 4), (26, 30), (40, 44), (255, 255)]);
+        let difference: Vec<_> = b.difference_iter(&a).collect();
+        assert_eq!(difference, vec![(11, 19), (31, 32), (51, 60)]);
+        assert_eq!(a.difference_iter(&a).count(), 0);
+        assert_eq!(a.union_iter(&RBSet::new()).count(), 4);
+    }
+}
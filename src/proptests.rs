@@ -0,0 +1,255 @@
+// Randomized operations checked against a HashSet, same idea as the fuzz target, but on every
+// `cargo test` run and covering range operations and the alternative backends as well.
+// Everything runs for u8 and i8, so that both unsigned and signed edge cases are hit.
+
+use std::{
+    collections::HashSet,
+    fmt::Debug,
+    ops::{AddAssign, SubAssign},
+};
+
+use num_traits::{Bounded, Num, NumCast, ToPrimitive};
+
+use crate::{
+    testing::{random_op, Rng},
+    BTreeRBSet, FrozenRBSet, Op, RBSet,
+};
+
+trait Value:
+    Num + Ord + AddAssign + SubAssign + Copy + ToPrimitive + NumCast + Bounded + Debug
+{
+}
+
+impl Value for u8 {}
+impl Value for i8 {}
+
+// oracle keeps values as i128 so that ranges can be iterated for any T
+type Oracle = HashSet<i128>;
+
+fn values<T: Value>(a: T, b: T) -> std::ops::RangeInclusive<i128> {
+    a.to_i128().unwrap()..=b.to_i128().unwrap()
+}
+
+fn apply_oracle<T: Value>(oracle: &mut Oracle, op: Op<T>) {
+    match op {
+        Op::Insert(x) => {
+            oracle.insert(x.to_i128().unwrap());
+        }
+        Op::Remove(x) => {
+            oracle.remove(&x.to_i128().unwrap());
+        }
+        Op::InsertRange(a, b) => oracle.extend(values(a, b)),
+        Op::RemoveRange(a, b) => oracle.retain(|x| !values(a, b).contains(x)),
+    }
+}
+
+fn apply<T: Value>(set: &mut RBSet<T>, op: Op<T>) {
+    match op {
+        Op::Insert(x) => set.insert(x),
+        Op::Remove(x) => set.remove(&x),
+        Op::InsertRange(a, b) => set.insert_range(a, b),
+        Op::RemoveRange(a, b) => set.remove_range(a, b),
+    }
+}
+
+fn sorted(oracle: &Oracle) -> Vec<i128> {
+    let mut values: Vec<i128> = oracle.iter().copied().collect();
+    values.sort();
+    values
+}
+
+// ranges that the oracle contents should be stored as
+fn expected_ranges<T: Value>(oracle: &Oracle) -> Vec<(T, T)> {
+    let values = sorted(oracle).into_iter().map(|x| T::from(x).unwrap());
+    RBSet::from_sorted_iter(values).ranges
+}
+
+fn operations_match_hash_set<T: Value>(seed: u64) {
+    let mut rng = Rng::new(seed);
+    for _ in 0..200 {
+        let mut set = RBSet::<T>::new();
+        let mut oracle = HashSet::new();
+        for _ in 0..100 {
+            let op = random_op(&mut rng);
+            apply(&mut set, op);
+            apply_oracle(&mut oracle, op);
+            assert_eq!(set.ranges(), expected_ranges::<T>(&oracle), "after {op:?}");
+            assert_eq!(set.len(), oracle.len());
+        }
+        for x in values(T::min_value(), T::max_value()) {
+            let value = T::from(x).unwrap();
+            assert_eq!(set.contains(&value), oracle.contains(&x));
+        }
+    }
+}
+
+#[test]
+fn operations_match_hash_set_u8() {
+    operations_match_hash_set::<u8>(0x5eed);
+}
+
+#[test]
+fn operations_match_hash_set_i8() {
+    operations_match_hash_set::<i8>(0x5eed);
+}
+
+fn positional_match_hash_set<T: Value>(seed: u64) {
+    let mut rng = Rng::new(seed);
+    for _ in 0..300 {
+        let mut set = RBSet::<T>::new();
+        let mut oracle = HashSet::new();
+        for _ in 0..20 {
+            let op = random_op(&mut rng);
+            apply(&mut set, op);
+            apply_oracle(&mut oracle, op);
+        }
+        let values: Vec<T> = sorted(&oracle)
+            .into_iter()
+            .map(|x| T::from(x).unwrap())
+            .collect();
+        let n = rng.next_u64() as usize % 300;
+        assert_eq!(set.select(n), values.get(n).copied());
+        let mut smallest = set.clone();
+        let taken: Vec<T> = smallest.take_smallest(n).iter().collect();
+        assert_eq!(taken, values[..n.min(values.len())]);
+        let mut largest = set.clone();
+        let taken: Vec<T> = largest.take_largest(n).iter().collect();
+        assert_eq!(taken, values[values.len().saturating_sub(n)..]);
+    }
+}
+
+#[test]
+fn positional_match_hash_set_u8() {
+    positional_match_hash_set::<u8>(0x5e1e);
+}
+
+#[test]
+fn positional_match_hash_set_i8() {
+    positional_match_hash_set::<i8>(0x5e1e);
+}
+
+fn batch_matches_sequential<T: Value>(seed: u64) {
+    let mut rng = Rng::new(seed);
+    for _ in 0..200 {
+        let mut set = RBSet::<T>::new();
+        for _ in 0..20 {
+            apply(&mut set, random_op(&mut rng));
+        }
+        let ops: Vec<Op<T>> = (0..50).map(|_| random_op(&mut rng)).collect();
+        let mut batched = set.clone();
+        batched.apply_ops(ops.iter().copied());
+        for op in &ops {
+            apply(&mut set, *op);
+        }
+        assert_eq!(batched.ranges(), set.ranges(), "after {ops:?}");
+    }
+}
+
+#[test]
+fn batch_matches_sequential_u8() {
+    batch_matches_sequential::<u8>(0xba7c);
+}
+
+#[test]
+fn batch_matches_sequential_i8() {
+    batch_matches_sequential::<i8>(0xba7c);
+}
+
+fn backends_match_rbset<T: Value>(seed: u64) {
+    let mut rng = Rng::new(seed);
+    for _ in 0..100 {
+        let mut set = RBSet::<T>::new();
+        let mut btree = BTreeRBSet::new();
+        let mut frozen = FrozenRBSet::new();
+        for _ in 0..100 {
+            let op = random_op(&mut rng);
+            apply(&mut set, op);
+            match op {
+                Op::Insert(x) => {
+                    btree.insert(x);
+                    frozen = frozen.with_inserted(x);
+                }
+                Op::Remove(x) => {
+                    btree.remove(&x);
+                    frozen = frozen.with_removed(&x);
+                }
+                Op::InsertRange(a, b) => {
+                    btree.insert_range(a, b);
+                    frozen = frozen.with_inserted_range(a, b);
+                }
+                Op::RemoveRange(a, b) => {
+                    btree.remove_range(a, b);
+                    frozen = frozen.with_removed_range(a, b);
+                }
+            }
+            assert_eq!(btree.to_set().ranges(), set.ranges(), "after {op:?}");
+            assert_eq!(frozen.to_set().ranges(), set.ranges(), "after {op:?}");
+            assert_eq!(frozen.len(), set.len());
+        }
+    }
+}
+
+#[test]
+fn backends_match_rbset_u8() {
+    backends_match_rbset::<u8>(0xba5e);
+}
+
+#[test]
+fn backends_match_rbset_i8() {
+    backends_match_rbset::<i8>(0xba5e);
+}
+
+fn set_algebra_matches_hash_set<T: Value>(seed: u64) {
+    let mut rng = Rng::new(seed);
+    for _ in 0..200 {
+        let mut sets = [RBSet::<T>::new(), RBSet::new()];
+        let mut oracles = [HashSet::new(), HashSet::new()];
+        for (set, oracle) in sets.iter_mut().zip(oracles.iter_mut()) {
+            for _ in 0..20 {
+                let op = random_op(&mut rng);
+                apply(set, op);
+                apply_oracle(oracle, op);
+            }
+        }
+        let [a, b] = &sets;
+        let [oa, ob] = &oracles;
+        let union: Oracle = oa.union(ob).copied().collect();
+        let intersection: Oracle = oa.intersection(ob).copied().collect();
+        let difference: Oracle = oa.difference(ob).copied().collect();
+        assert_eq!(a.union(b).ranges(), expected_ranges::<T>(&union));
+        assert_eq!(
+            a.intersection(b).ranges(),
+            expected_ranges::<T>(&intersection)
+        );
+        assert_eq!(a.difference(b).ranges(), expected_ranges::<T>(&difference));
+        assert_eq!(a.union_len(b), union.len());
+        assert_eq!(a.difference_len(b), difference.len());
+        let mut patched = a.clone();
+        patched.apply(&a.diff(b));
+        assert_eq!(patched.ranges(), b.ranges());
+    }
+}
+
+#[test]
+fn set_algebra_matches_hash_set_u8() {
+    set_algebra_matches_hash_set::<u8>(0xa1e0);
+}
+
+#[test]
+fn set_algebra_matches_hash_set_i8() {
+    set_algebra_matches_hash_set::<i8>(0xa1e0);
+}
+
+#[test]
+fn random_sets_are_valid() {
+    let mut rng = Rng::new(7);
+    for _ in 0..100 {
+        let set = crate::testing::random_set::<i8>(&mut rng, 10);
+        assert!(RBSet::try_from(set.ranges().to_vec()).unwrap() == set);
+        assert_eq!(
+            set.ranges(),
+            RBSet::from_sorted_iter(set.iter()).ranges(),
+            "not coalesced"
+        );
+    }
+}
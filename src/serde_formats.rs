@@ -0,0 +1,160 @@
+//! Alternative serde representations of RBSet, for use with `#[serde(with = "...")]`.
+//!
+//! ```
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Received {
+//!     #[serde(with = "rbset::serde_formats::compact")]
+//!     chunks: rbset::RBSet<u32>,
+//! }
+//! ```
+
+/// Sequence of all values in ascending order: `[0, 1, 2, 3, 7, 8]`.
+pub mod values {
+    use std::ops::{AddAssign, SubAssign};
+
+    use num_traits::{Num, ToPrimitive};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::RBSet;
+
+    pub fn serialize<T, S>(set: &RBSet<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive + Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(set.iter())
+    }
+
+    /// Values can be in any order and repeat.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<RBSet<T>, D::Error>
+    where
+        T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive + Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let mut values = Vec::<T>::deserialize(deserializer)?;
+        Ok(RBSet::from_unsorted(&mut values))
+    }
+}
+
+/// Sequence of [start, end] pairs: `[[0, 3], [7, 8]]`.
+pub mod ranges {
+    use std::ops::{AddAssign, SubAssign};
+
+    use num_traits::{Num, ToPrimitive};
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::RBSet;
+
+    pub fn serialize<T, S>(set: &RBSet<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        set.ranges.serialize(serializer)
+    }
+
+    /// Ranges can be in any order and overlap.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<RBSet<T>, D::Error>
+    where
+        T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive + Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let ranges = Vec::<(T, T)>::deserialize(deserializer)?;
+        RBSet::try_from(ranges).map_err(D::Error::custom)
+    }
+}
+
+/// String of comma separated ranges and single values: `"0..=3,7..=8,10"`.
+pub mod compact {
+    use std::{
+        fmt::{Display, Write},
+        ops::{AddAssign, SubAssign},
+        str::FromStr,
+    };
+
+    use num_traits::{Num, ToPrimitive};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use crate::RBSet;
+
+    pub fn serialize<T, S>(set: &RBSet<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: PartialEq + Display,
+        S: Serializer,
+    {
+        serializer.serialize_str(&format(set))
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<RBSet<T>, D::Error>
+    where
+        T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive + FromStr,
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        parse(&s).ok_or_else(|| D::Error::custom("invalid compact RBSet string"))
+    }
+
+    fn format<T: PartialEq + Display>(set: &RBSet<T>) -> String {
+        let mut s = String::new();
+        for (idx, (start, end)) in set.ranges.iter().enumerate() {
+            if idx > 0 {
+                s.push(',');
+            }
+            if start == end {
+                let _ = write!(s, "{start}");
+            } else {
+                let _ = write!(s, "{start}..={end}");
+            }
+        }
+        s
+    }
+
+    fn parse<T>(s: &str) -> Option<RBSet<T>>
+    where
+        T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive + FromStr,
+    {
+        let mut ranges = Vec::new();
+        for part in s.split(',').filter(|part| !part.is_empty()) {
+            let (start, end) = part.split_once("..=").unwrap_or((part, part));
+            ranges.push((start.trim().parse().ok()?, end.trim().parse().ok()?));
+        }
+        RBSet::try_from(ranges).ok()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn format_parse() {
+            let set = RBSet::<i32>::try_from(vec![(-5, -3), (0, 0), (7, 8)]).unwrap();
+            assert_eq!(format(&set), "-5..=-3,0,7..=8");
+            assert_eq!(
+                parse::<i32>("-5..=-3,0,7..=8").unwrap().ranges(),
+                set.ranges()
+            );
+            assert_eq!(
+                parse::<i32>("7..=8, 0 ,-5..=-4,-3").unwrap().ranges(),
+                set.ranges()
+            );
+            assert!(parse::<i32>("").unwrap().is_empty());
+            assert!(parse::<i32>("3..=1").is_none());
+            assert!(parse::<u8>("1..=300").is_none());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::{value::SeqDeserializer, IntoDeserializer};
+
+    use super::*;
+
+    #[test]
+    fn values_deserialize() {
+        let deserializer: SeqDeserializer<_, serde::de::value::Error> =
+            vec![8u32, 0, 1, 7, 2, 3, 1].into_deserializer();
+        let set = values::deserialize(deserializer).unwrap();
+        assert_eq!(set.ranges(), &[(0, 3), (7, 8)]);
+    }
+}
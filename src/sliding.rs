@@ -0,0 +1,130 @@
+use std::ops::{AddAssign, Deref, SubAssign};
+
+use num_traits::{Num, ToPrimitive};
+
+use crate::{distance, RBSet};
+
+/// RBSet that only keeps values within width of the largest inserted value, like a receive window.
+///
+/// Inserting a new maximum drops everything below max - width, values below the window are
+/// rejected.
+#[derive(Clone, Debug)]
+pub struct SlidingRBSet<T> {
+    set: RBSet<T>,
+    width: T,
+}
+
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> SlidingRBSet<T> {
+    /// Panics if width is negative.
+    pub fn new(width: T) -> Self {
+        assert!(width >= T::zero(), "width must be >= 0");
+        SlidingRBSet {
+            set: RBSet::new(),
+            width,
+        }
+    }
+
+    pub fn width(&self) -> T {
+        self.width
+    }
+
+    /// Returns false if value is below the window and was not inserted.
+    pub fn insert(&mut self, value: T) -> bool {
+        let Some(max) = self.max() else {
+            self.set.insert(value);
+            return true;
+        };
+        if value > max {
+            self.set.insert(value);
+            self.slide(value);
+            true
+        } else if !self.in_window(&value) {
+            false
+        } else {
+            self.set.insert(value);
+            true
+        }
+    }
+
+    /// Insert all values in [start, end] that are within the window after inserting end.
+    pub fn insert_range(&mut self, start: T, end: T) {
+        if start > end {
+            return;
+        }
+        let max = match self.max() {
+            Some(max) if max > end => max,
+            _ => end,
+        };
+        self.set.insert_range(start, end);
+        self.slide(max);
+    }
+
+    /// Value is not below the window, empty window accepts everything.
+    pub fn in_window(&self, value: &T) -> bool {
+        match self.max() {
+            Some(max) => *value >= max || distance(*value, max) <= self.width_u128(),
+            None => true,
+        }
+    }
+
+    pub fn max(&self) -> Option<T> {
+        self.set.ranges().last().map(|range| range.1)
+    }
+
+    pub fn into_inner(self) -> RBSet<T> {
+        self.set
+    }
+
+    // drop everything below max - width, without computing it if it would overflow
+    fn slide(&mut self, max: T) {
+        let min = self.set.ranges()[0].0;
+        if distance(min, max) > self.width_u128() {
+            self.set.remove_up_to(max - self.width - T::one());
+        }
+    }
+
+    fn width_u128(&self) -> u128 {
+        distance(T::zero(), self.width)
+    }
+}
+
+impl<T> Deref for SlidingRBSet<T> {
+    type Target = RBSet<T>;
+
+    fn deref(&self) -> &RBSet<T> {
+        &self.set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slides() {
+        let mut set = SlidingRBSet::<u32>::new(10);
+        assert!(set.insert(5));
+        assert!(set.insert(1));
+        assert!(set.insert(12));
+        // window is [2, 12]
+        assert_eq!(set.ranges(), &[(5, 5), (12, 12)]);
+        assert!(!set.insert(1));
+        assert!(set.insert(2));
+        assert!(!set.in_window(&1));
+        set.insert_range(13, 20);
+        assert_eq!(set.ranges(), &[(12, 20)]);
+        assert!(set.in_window(&10));
+        assert!(set.in_window(&100));
+    }
+
+    #[test]
+    fn no_overflow() {
+        let mut set = SlidingRBSet::<i8>::new(100);
+        set.insert_range(-128, -100);
+        set.insert(127);
+        assert_eq!(set.ranges(), &[(127, 127)]);
+        let mut set = SlidingRBSet::<u8>::new(255);
+        set.insert_range(0, 255);
+        assert_eq!(set.len(), 256);
+    }
+}
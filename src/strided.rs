@@ -0,0 +1,152 @@
+use std::ops::{AddAssign, SubAssign};
+
+use num_traits::{Num, ToPrimitive};
+
+use crate::{Error, RBSet};
+
+/// Set of values that are all multiples of a fixed stride, e.g. block aligned byte offsets.
+///
+/// Indices (value / stride) are stored, so consecutive blocks form one range. All methods
+/// take and return real values.
+#[derive(Clone, Debug)]
+pub struct StridedRBSet<T> {
+    indices: RBSet<T>,
+    stride: T,
+}
+
+impl<T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> StridedRBSet<T> {
+    /// Panics if stride is not positive.
+    pub fn new(stride: T) -> Self {
+        assert!(stride > T::zero(), "stride must be > 0");
+        StridedRBSet {
+            indices: RBSet::new(),
+            stride,
+        }
+    }
+
+    pub fn stride(&self) -> T {
+        self.stride
+    }
+
+    /// Fails if value is not a multiple of the stride.
+    pub fn insert(&mut self, value: T) -> Result<(), Error> {
+        if value % self.stride != T::zero() {
+            return Err(Error::NotOnStride);
+        }
+        self.indices.insert(value / self.stride);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, value: &T) {
+        if *value % self.stride == T::zero() {
+            self.indices.remove(&(*value / self.stride));
+        }
+    }
+
+    /// Insert all multiples of the stride in [start, end].
+    pub fn insert_range(&mut self, start: T, end: T) {
+        if start <= end {
+            self.indices
+                .insert_range(self.ceil_index(start), self.floor_index(end));
+        }
+    }
+
+    /// Remove all multiples of the stride in [start, end].
+    pub fn remove_range(&mut self, start: T, end: T) {
+        if start <= end {
+            self.indices
+                .remove_range(self.ceil_index(start), self.floor_index(end));
+        }
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        *value % self.stride == T::zero() && self.indices.contains(&(*value / self.stride))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.indices.clear();
+    }
+
+    /// First and last value of every run of consecutive multiples.
+    pub fn ranges(&self) -> impl DoubleEndedIterator<Item = (T, T)> + '_ {
+        self.indices
+            .iter_ranges()
+            .map(|(start, end)| (start * self.stride, end * self.stride))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        self.indices.iter().map(|idx| idx * self.stride)
+    }
+
+    /// Underlying set of value / stride.
+    pub fn indices(&self) -> &RBSet<T> {
+        &self.indices
+    }
+
+    // index of the smallest multiple >= value
+    fn ceil_index(&self, value: T) -> T {
+        let idx = value / self.stride;
+        // division truncates towards zero, so only positive values need rounding up
+        if value % self.stride > T::zero() {
+            idx + T::one()
+        } else {
+            idx
+        }
+    }
+
+    // index of the largest multiple <= value
+    fn floor_index(&self, value: T) -> T {
+        let idx = value / self.stride;
+        if value % self.stride < T::zero() {
+            idx - T::one()
+        } else {
+            idx
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks() {
+        let mut set = StridedRBSet::<u64>::new(512);
+        set.insert(0).unwrap();
+        set.insert(512).unwrap();
+        set.insert(2048).unwrap();
+        assert_eq!(set.insert(100), Err(Error::NotOnStride));
+        assert_eq!(
+            set.ranges().collect::<Vec<_>>(),
+            vec![(0, 512), (2048, 2048)]
+        );
+        assert_eq!(set.indices().ranges(), &[(0, 1), (4, 4)]);
+        assert!(set.contains(&512));
+        assert!(!set.contains(&513));
+        // bytes [1000, 4095] contain the starts of blocks 1024..=3584
+        set.insert_range(1000, 4095);
+        assert_eq!(set.ranges().collect::<Vec<_>>(), vec![(0, 3584)]);
+        set.remove_range(1, 1535);
+        assert_eq!(set.iter().take(3).collect::<Vec<_>>(), vec![0, 1536, 2048]);
+        assert_eq!(set.len(), 6);
+    }
+
+    #[test]
+    fn negative() {
+        let mut set = StridedRBSet::<i32>::new(10);
+        set.insert_range(-25, 25);
+        assert_eq!(set.ranges().collect::<Vec<_>>(), vec![(-20, 20)]);
+        set.remove_range(-11, -9);
+        assert_eq!(set.ranges().collect::<Vec<_>>(), vec![(-20, -20), (0, 20)]);
+        set.insert_range(-9, -1);
+        assert_eq!(set.len(), 4);
+    }
+}
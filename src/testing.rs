@@ -0,0 +1,87 @@
+//! Random sets and operations for property tests of code built on RBSet, dependency-free so it
+//! works with any test framework. Generated values cover the whole domain of T, ranges are
+//! mostly short, sometimes reaching to T::MAX.
+
+use std::ops::{AddAssign, SubAssign};
+
+use num_traits::{Bounded, Num, NumCast, ToPrimitive};
+
+use crate::{distance, offset, Op, RBSet};
+
+/// xorshift64*, deterministic for a given seed.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck at 0
+        Rng {
+            state: if seed == 0 {
+                0x9e37_79b9_7f4a_7c15
+            } else {
+                seed
+            },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn next_u128(&mut self) -> u128 {
+        (self.next_u64() as u128) << 64 | self.next_u64() as u128
+    }
+
+    // random number in [0, n]
+    fn up_to(&mut self, n: u128) -> u128 {
+        match n.checked_add(1) {
+            Some(n) => self.next_u128() % n,
+            None => self.next_u128(),
+        }
+    }
+}
+
+/// Op with values anywhere in T.
+pub fn random_op<T>(rng: &mut Rng) -> Op<T>
+where
+    T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive + NumCast + Bounded,
+{
+    let a = random_value(rng, T::min_value(), T::max_value());
+    let room = distance(a, T::max_value());
+    let len = if rng.up_to(7) == 0 {
+        rng.up_to(room)
+    } else {
+        rng.up_to(7).min(room)
+    };
+    let b = offset(a, len);
+    match rng.up_to(3) {
+        0 => Op::Insert(a),
+        1 => Op::Remove(a),
+        2 => Op::InsertRange(a, b),
+        _ => Op::RemoveRange(a, b),
+    }
+}
+
+/// Value in [lo, hi], panics if lo > hi.
+pub fn random_value<T>(rng: &mut Rng, lo: T, hi: T) -> T
+where
+    T: Num + PartialOrd + Copy + ToPrimitive + NumCast,
+{
+    assert!(lo <= hi, "lo must be <= hi");
+    offset(lo, rng.up_to(distance(lo, hi)))
+}
+
+/// Set built by applying ops random operations to an empty set.
+pub fn random_set<T>(rng: &mut Rng, ops: usize) -> RBSet<T>
+where
+    T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive + NumCast + Bounded,
+{
+    let mut set = RBSet::new();
+    set.apply_ops((0..ops).map(|_| random_op(rng)));
+    set
+}
@@ -0,0 +1,280 @@
+use std::ops::{AddAssign, RangeBounds, RangeInclusive, SubAssign};
+
+use num_traits::{Bounded, Num, ToPrimitive};
+
+use crate::{
+    check_ranges, inclusive_bounds, len_of, DifferenceRanges, Error, IntersectionRanges, RBSet,
+    RBSetIter, UnionRanges,
+};
+
+/// Read-only set over borrowed ranges, see [RBSet::as_view].
+///
+/// Can be built in const context for static sets:
+/// ```
+/// use rbset::RBSetRef;
+///
+/// static ALLOWED: RBSetRef<'static, u16> =
+///     RBSetRef::from_sorted_ranges_unchecked(&[(1, 5), (10, 20)]);
+/// assert!(ALLOWED.contains(&12));
+/// ```
+#[derive(Debug)]
+pub struct RBSetView<'a, T> {
+    // [start, end]
+    ranges: &'a [(T, T)],
+}
+
+impl<'a, T> RBSetView<'a, T> {
+    /// Caller must guarantee the same invariants as for
+    /// [RBSet::from_sorted_ranges_unchecked], see [RBSetView::new] for a checked version.
+    pub const fn from_sorted_ranges_unchecked(ranges: &'a [(T, T)]) -> Self {
+        RBSetView { ranges }
+    }
+
+    pub const fn ranges(&self) -> &'a [(T, T)] {
+        self.ranges
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+impl<'a, T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> RBSetView<'a, T> {
+    /// Checks that ranges are sorted and coalesced.
+    pub fn new(ranges: &'a [(T, T)]) -> Result<Self, Error> {
+        check_ranges(ranges.iter().copied())?;
+        Ok(RBSetView { ranges })
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        let idx = self.ranges.partition_point(|r| r.1 < *value);
+        self.ranges.get(idx).is_some_and(|r| r.0 <= *value)
+    }
+
+    pub fn len(&self) -> usize {
+        len_of(self.ranges.iter().copied())
+    }
+
+    pub fn iter(&self) -> RBSetIter<'a, T> {
+        RBSetIter {
+            ranges: self.ranges,
+            pos: 0,
+            last_yielded: None,
+        }
+    }
+
+    /// Missing values between stored ranges.
+    pub fn gaps(&self) -> impl Iterator<Item = RangeInclusive<T>> + 'a {
+        self.ranges
+            .windows(2)
+            .map(|w| w[0].1 + T::one()..=w[1].0 - T::one())
+    }
+
+    pub fn union(&self, other: &RBSet<T>) -> RBSet<T> {
+        RBSet::from_sorted_ranges_unchecked(
+            UnionRanges::new(self.ranges.iter().copied(), other.ranges.iter().copied()).collect(),
+        )
+    }
+
+    pub fn intersection(&self, other: &RBSet<T>) -> RBSet<T> {
+        RBSet::from_sorted_ranges_unchecked(
+            IntersectionRanges::new(self.ranges.iter().copied(), other.ranges.iter().copied())
+                .collect(),
+        )
+    }
+
+    /// Values in self, but not in other.
+    pub fn difference(&self, other: &RBSet<T>) -> RBSet<T> {
+        RBSet::from_sorted_ranges_unchecked(
+            DifferenceRanges::new(self.ranges.iter().copied(), other.ranges.iter().copied())
+                .collect(),
+        )
+    }
+
+    pub fn to_set(&self) -> RBSet<T> {
+        RBSet::from_sorted_ranges_unchecked(self.ranges.to_vec())
+    }
+}
+
+/// Alias of [RBSetView], reads better for static sets.
+pub type RBSetRef<'a, T> = RBSetView<'a, T>;
+
+impl<'a, T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive + Bounded>
+    RBSetView<'a, T>
+{
+    /// Restrict to values within bounds, without copying.
+    pub fn view_range(&self, bounds: impl RangeBounds<T>) -> RBSetWindow<'a, T> {
+        let Some((start, end)) = inclusive_bounds(bounds) else {
+            return RBSetWindow {
+                ranges: &[],
+                start: T::zero(),
+                end: T::zero(),
+            };
+        };
+        let first = self.ranges.partition_point(|r| r.1 < start);
+        let last = self.ranges.partition_point(|r| r.0 <= end).max(first);
+        RBSetWindow {
+            ranges: &self.ranges[first..last],
+            start,
+            end,
+        }
+    }
+}
+
+/// Part of a set within some bounds, see [RBSet::view_range].
+///
+/// Ranges crossing the bounds are clamped when they are read.
+#[derive(Debug)]
+pub struct RBSetWindow<'a, T> {
+    // ranges overlapping [start, end]
+    ranges: &'a [(T, T)],
+    start: T,
+    end: T,
+}
+
+impl<'a, T: Num + PartialOrd + AddAssign + SubAssign + Copy + ToPrimitive> RBSetWindow<'a, T> {
+    pub fn contains(&self, value: &T) -> bool {
+        if *value < self.start || *value > self.end {
+            return false;
+        }
+        let idx = self.ranges.partition_point(|r| r.1 < *value);
+        self.ranges.get(idx).is_some_and(|r| r.0 <= *value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        len_of(self.ranges())
+    }
+
+    /// Ranges clamped to the bounds.
+    pub fn ranges(&self) -> impl DoubleEndedIterator<Item = (T, T)> + 'a {
+        let (lo, hi) = (self.start, self.end);
+        self.ranges.iter().map(move |&(start, end)| {
+            (
+                if start < lo { lo } else { start },
+                if end > hi { hi } else { end },
+            )
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = T> + 'a {
+        self.ranges().flat_map(|(start, end)| {
+            std::iter::successors(Some(start), move |&x| (x < end).then(|| x + T::one()))
+        })
+    }
+
+    pub fn to_set(&self) -> RBSet<T> {
+        RBSet::from_sorted_ranges_unchecked(self.ranges().collect())
+    }
+}
+
+impl<T: Copy> Clone for RBSetWindow<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Copy> Copy for RBSetWindow<'_, T> {}
+
+impl<T> Clone for RBSetView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for RBSetView<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static ALLOWED: RBSetRef<'static, i16> =
+        RBSetRef::from_sorted_ranges_unchecked(&[(-10, -5), (1, 1), (10, 20)]);
+
+    #[test]
+    fn static_set() {
+        assert!(ALLOWED.contains(&-7));
+        assert!(ALLOWED.contains(&1));
+        assert!(!ALLOWED.contains(&2));
+        assert_eq!(ALLOWED.len(), 18);
+        assert_eq!(
+            ALLOWED.iter().take(3).collect::<Vec<_>>(),
+            vec![-10, -9, -8]
+        );
+        assert!(RBSetView::new(ALLOWED.ranges()).is_ok());
+    }
+
+    #[test]
+    fn checked() {
+        assert_eq!(
+            RBSetView::new(&[(0u8, 3), (4, 5)]).unwrap_err(),
+            Error::NotCoalesced { index: 1 }
+        );
+        assert_eq!(
+            RBSetView::new(&[(0u8, 3), (2, 5)]).unwrap_err(),
+            Error::NotCoalesced { index: 1 }
+        );
+        assert_eq!(
+            RBSetView::new(&[(3u8, 0)]).unwrap_err(),
+            Error::StartAfterEnd { index: 0 }
+        );
+        let set = RBSet::try_from(vec![(0u8, 3), (5, 5)]).unwrap();
+        assert_eq!(set.as_view().ranges(), set.ranges());
+    }
+
+    #[test]
+    fn gaps() {
+        assert_eq!(ALLOWED.gaps().collect::<Vec<_>>(), vec![-4..=0, 2..=9]);
+        assert_eq!(
+            RBSetView::<u8>::from_sorted_ranges_unchecked(&[])
+                .gaps()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn set_algebra() {
+        let a = RBSet::<i32>::try_from(vec![(0, 10), (20, 30), (40, 50)]).unwrap();
+        let b = RBSet::<i32>::try_from(vec![(-5, 2), (5, 25), (31, 39), (45, 60)]).unwrap();
+        assert_eq!(a.as_view().union(&b).ranges(), &[(-5, 60)]);
+        assert_eq!(
+            a.as_view().intersection(&b).ranges(),
+            &[(0, 2), (5, 10), (20, 25), (45, 50)]
+        );
+        assert_eq!(
+            a.as_view().difference(&b).ranges(),
+            &[(3, 4), (26, 30), (40, 44)]
+        );
+        assert_eq!(a.union(&RBSet::new()).ranges(), a.ranges());
+        assert!(a.intersection(&RBSet::new()).is_empty());
+    }
+
+    #[test]
+    fn view_range() {
+        let set = RBSet::<u8>::try_from(vec![(0, 3), (7, 8), (10, 20), (250, 255)]).unwrap();
+        let window = set.view_range(2..12);
+        assert_eq!(
+            window.ranges().collect::<Vec<_>>(),
+            vec![(2, 3), (7, 8), (10, 11)]
+        );
+        assert_eq!(window.len(), 6);
+        assert!(window.contains(&11));
+        assert!(!window.contains(&12));
+        assert!(!window.contains(&0));
+        assert_eq!(window.iter().collect::<Vec<_>>(), vec![2, 3, 7, 8, 10, 11]);
+
+        assert_eq!(set.view_range(..).len(), set.len());
+        assert_eq!(
+            set.view_range(253..).ranges().collect::<Vec<_>>(),
+            vec![(253, 255)]
+        );
+        assert!(set.view_range(4..=6).is_empty());
+        assert!(set.view_range(..0).is_empty());
+        assert!(set.view_range(5..5).is_empty());
+        assert_eq!(set.view_range(..=0).to_set().ranges(), &[(0, 0)]);
+    }
+}